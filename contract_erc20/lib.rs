@@ -0,0 +1,127 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Minimal ERC-20 style token used to test multi-asset payouts of the treasury
+/// against a locally deployed token contract.
+#[ink::contract]
+pub mod erc20 {
+    use ink::storage::Mapping;
+    use ink::{H160, U256};
+
+    #[ink(storage)]
+    #[derive(Default)]
+    pub struct Erc20 {
+        total_supply: U256,
+        balances: Mapping<H160, U256>,
+        allowances: Mapping<(H160, H160), U256>,
+    }
+
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        from: H160, // Zero address when minting
+        #[ink(topic)]
+        to: H160,
+        value: U256,
+    }
+
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        owner: H160,
+        #[ink(topic)]
+        spender: H160,
+        value: U256,
+    }
+
+    /// Custom errors for the token contract
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Not enough balance to fulfill the transfer
+        InsufficientBalance,
+        /// Not enough allowance to fulfill `transfer_from`
+        InsufficientAllowance,
+    }
+
+    /// Type alias for the contract's result type
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl Erc20 {
+        /// Creates a token with the whole supply owned by the caller
+        #[ink(constructor)]
+        pub fn new(total_supply: U256) -> Self {
+            let mut balances = Mapping::default();
+            let caller = Self::env().caller();
+            balances.insert(caller, &total_supply);
+            Self::env().emit_event(Transfer {
+                from: H160::zero(),
+                to: caller,
+                value: total_supply,
+            });
+            Self {
+                total_supply,
+                balances,
+                allowances: Default::default(),
+            }
+        }
+
+        #[ink(message)]
+        pub fn total_supply(&self) -> U256 {
+            self.total_supply
+        }
+
+        #[ink(message)]
+        pub fn balance_of(&self, owner: H160) -> U256 {
+            self.balances.get(owner).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn allowance(&self, owner: H160, spender: H160) -> U256 {
+            self.allowances.get((owner, spender)).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn transfer(&mut self, to: H160, value: U256) -> Result<()> {
+            let from = self.env().caller();
+            self.transfer_from_to(from, to, value)
+        }
+
+        #[ink(message)]
+        pub fn approve(&mut self, spender: H160, value: U256) -> Result<()> {
+            let owner = self.env().caller();
+            self.allowances.insert((owner, spender), &value);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn transfer_from(&mut self, from: H160, to: H160, value: U256) -> Result<()> {
+            let caller = self.env().caller();
+            let allowance = self.allowance(from, caller);
+            if allowance < value {
+                return Err(Error::InsufficientAllowance);
+            }
+            self.transfer_from_to(from, to, value)?;
+            self.allowances
+                .insert((from, caller), &allowance.saturating_sub(value));
+            Ok(())
+        }
+
+        fn transfer_from_to(&mut self, from: H160, to: H160, value: U256) -> Result<()> {
+            let from_balance = self.balance_of(from);
+            if from_balance < value {
+                return Err(Error::InsufficientBalance);
+            }
+            self.balances
+                .insert(from, &from_balance.saturating_sub(value));
+            let to_balance = self.balance_of(to);
+            self.balances.insert(to, &to_balance.saturating_add(value));
+            self.env().emit_event(Transfer { from, to, value });
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,77 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Mock price oracle used to test fiat-denominated payouts of the treasury.
+/// Prices are set by the owner and reported together with the block they were set in.
+#[ink::contract]
+pub mod price_oracle {
+    use ink::storage::Mapping;
+    use ink::{H160, U256};
+
+    /// Asset address and ISO 4217 currency code a price is quoted for
+    pub type PriceKey = (H160, [u8; 3]);
+    /// Price and the block it was set in
+    pub type PriceEntry = (U256, u32);
+
+    #[ink(storage)]
+    pub struct PriceOracle {
+        owner: H160,
+        prices: Mapping<PriceKey, PriceEntry>,
+    }
+
+    #[ink(event)]
+    pub struct PriceUpdated {
+        #[ink(topic)]
+        asset: H160, // Zero address for the native currency
+        currency: [u8; 3],
+        price: U256,
+    }
+
+    /// Custom errors for the oracle contract
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        /// Caller is not the owner
+        NotOwner,
+    }
+
+    /// Type alias for the contract's result type
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    impl Default for PriceOracle {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl PriceOracle {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                prices: Mapping::default(),
+            }
+        }
+
+        /// Set the price of one `currency` unit in base units of `asset`
+        #[ink(message)]
+        pub fn set_price(&mut self, asset: H160, currency: [u8; 3], price: U256) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            let block = self.env().block_number();
+            self.prices.insert((asset, currency), &(price, block));
+            self.env().emit_event(PriceUpdated {
+                asset,
+                currency,
+                price,
+            });
+            Ok(())
+        }
+
+        /// Latest price of one `currency` unit in base units of `asset` and the block it was set in
+        #[ink(message)]
+        pub fn latest_price(&self, asset: H160, currency: [u8; 3]) -> Option<PriceEntry> {
+            self.prices.get((asset, currency))
+        }
+    }
+}
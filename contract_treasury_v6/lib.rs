@@ -7,10 +7,14 @@ use parity_scale_codec::{Decode, Encode};
 #[ink::contract]
 pub mod treasuryflow {
     use super::*;
+    use ink::env::hash::Keccak256;
     use ink::{H160, U256};
 
     const MAX_APPROVALS: usize = 10;
 
+    /// Domain tag mixed into every off-chain approval payload
+    const APPROVAL_DOMAIN: &[u8] = b"treasuryflow:approve";
+
     #[derive(Debug, Clone, PartialEq)]
     #[ink::scale_derive(Encode, Decode, TypeInfo)]
     #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
@@ -28,6 +32,7 @@ pub mod treasuryflow {
         Active = 1,
         Completed = 2,
         Cancelled = 3,
+        Expired = 4,
     }
 
     /// Represents a payout
@@ -47,6 +52,12 @@ pub mod treasuryflow {
         block_number: BlockNumber,
         /// Treasurers who approved this payout
         approvals: Vec<H160>,
+        /// Block number at which each entry in `approvals` was given
+        approval_blocks: Vec<BlockNumber>,
+        /// Last block to reach the approval threshold (0 for no deadline)
+        deadline: BlockNumber,
+        /// Treasurers who voted against this payout
+        rejections: Vec<H160>,
         /// Type of payout
         payout_type: PayoutType,
         /// Status of the payout
@@ -63,6 +74,49 @@ pub mod treasuryflow {
         cancellation_approvals: Vec<H160>,
     }
 
+    /// Flat payout record read by the typed treasury's `import_v6_payouts`
+    #[derive(Debug, Clone, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub struct PayoutExport {
+        /// Unique identifier for the payout
+        pub id: u32,
+        /// Recipient of the payout
+        pub to: H160,
+        /// Amount paid out per installment
+        pub amount: U256,
+        /// Type of payout
+        pub payout_type: PayoutType,
+        /// Status of the payout
+        pub status: PayoutStatus,
+        /// Block number when payout was added
+        pub block_number: BlockNumber,
+        /// For recurring/vested: interval between payouts in blocks (0 for one-time)
+        pub interval_blocks: BlockNumber,
+        /// For recurring/vested: total number of payouts (0 for one-time)
+        pub total_payouts: u32,
+        /// For recurring/vested: number of payouts completed
+        pub completed_payouts: u32,
+        /// For vested: cliff period in blocks (0 for no cliff)
+        pub cliff_blocks: BlockNumber,
+        /// Approvals still counting towards the threshold
+        pub approvals: u32,
+        /// Approvals the payout needs before it is paid
+        pub required_approvals: u32,
+    }
+
+    /// Approval power a treasurer handed to another address for a block range
+    #[derive(Debug, Clone, PartialEq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Delegation {
+        /// Address approving on behalf of the delegator
+        pub delegate: H160,
+        /// First block the delegation is active
+        pub start_block: BlockNumber,
+        /// Last block the delegation is active
+        pub end_block: BlockNumber,
+    }
+
     /// Represents a threshold for treasurer approvals
     #[derive(Debug, Encode, Decode, Clone)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
@@ -94,6 +148,15 @@ pub mod treasuryflow {
         processing: bool,
         /// Next payout id
         next_payout_id: u32,
+        /// Nonce per payout for off-chain signed approvals, bumped whenever a vote
+        /// is withdrawn so earlier signatures cannot bring it back (replay protection)
+        approval_nonces: Mapping<u32, u32>,
+        /// Blocks a new proposal has to reach its threshold in (0 for no expiry)
+        proposal_expiry_blocks: BlockNumber,
+        /// Blocks after which an approval stops counting (0 for no limit)
+        approval_max_age_blocks: BlockNumber,
+        /// Approval delegations, keyed by the delegating treasurer
+        delegations: Mapping<H160, Delegation>,
     }
 
     /// Custom errors for the treasury contract
@@ -117,6 +180,26 @@ pub mod treasuryflow {
         PayoutNotFound = 6,
         /// Reentrancy detected
         Reentrancy = 7,
+        /// Signature could not be recovered to a signer
+        InvalidSignature = 8,
+        /// Payout proposal passed its deadline
+        PayoutExpired = 9,
+        /// Caller has not approved this payout
+        NotApproved = 10,
+        /// Treasurers cannot delegate to themselves
+        SelfDelegation = 11,
+        /// Delegate has delegated or is a delegate itself
+        DelegationChain = 12,
+        /// Delegation block range is empty or already over
+        InvalidDelegationPeriod = 13,
+        /// Caller has no delegation
+        NoDelegation = 14,
+        /// Approval nonce of the payout is exhausted
+        NonceOverflow = 15,
+        /// Payout already reached its threshold and started paying out
+        PayoutAlreadyActive = 16,
+        /// Payout already holds the maximum number of approvals
+        ApprovalsFull = 17,
     }
 
     /// Type alias for the contract's result type
@@ -156,6 +239,54 @@ pub mod treasuryflow {
         payouts_count: u32,
     }
 
+    #[ink(event)]
+    pub struct SignedApprovalsRecorded {
+        #[ink(topic)]
+        payout_id: u32,
+        approvers: Vec<H160>,
+        nonce: u32,
+    }
+
+    #[ink(event)]
+    pub struct PayoutExpired {
+        #[ink(topic)]
+        payout_id: u32,
+        deadline: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct ApprovalRevoked {
+        #[ink(topic)]
+        payout_id: u32,
+        #[ink(topic)]
+        treasurer: H160,
+    }
+
+    #[ink(event)]
+    pub struct PayoutRejected {
+        #[ink(topic)]
+        payout_id: u32,
+        rejections: u32,
+    }
+
+    #[ink(event)]
+    pub struct ApprovalDelegated {
+        #[ink(topic)]
+        delegator: H160,
+        #[ink(topic)]
+        delegate: H160,
+        start_block: BlockNumber,
+        end_block: BlockNumber,
+    }
+
+    #[ink(event)]
+    pub struct ApprovalUndelegated {
+        #[ink(topic)]
+        delegator: H160,
+        #[ink(topic)]
+        delegate: H160,
+    }
+
     #[ink(event)]
     pub struct PayoutCancelled {
         #[ink(topic)]
@@ -207,6 +338,10 @@ pub mod treasuryflow {
                 thresholds,
                 processing: false,
                 next_payout_id: 1,
+                approval_nonces: Mapping::new(),
+                proposal_expiry_blocks: 0,
+                approval_max_age_blocks: 0,
+                delegations: Mapping::new(),
             })
         }
 
@@ -245,6 +380,21 @@ pub mod treasuryflow {
             }
 
             if self.treasurers.remove(&treasurer) {
+                // Votes on open proposals leave with the treasurer
+                let mut withdrawn = Vec::new();
+                for payout in self.pending_payouts.iter_mut() {
+                    if payout.status == PayoutStatus::Pending {
+                        if Self::remove_approval(payout, treasurer) {
+                            withdrawn.push(payout.id);
+                        }
+                        payout.rejections.retain(|r| *r != treasurer);
+                    }
+                }
+                // Keep old signatures from restoring the approvals if the treasurer returns,
+                // an exhausted nonce must not block the removal
+                for payout_id in withdrawn {
+                    let _ = self.bump_approval_nonce(payout_id);
+                }
                 self.env().emit_event(TreasurerRemoved { treasurer });
             }
 
@@ -322,15 +472,27 @@ pub mod treasuryflow {
             let payout_id = self.next_payout_id;
             self.next_payout_id = self.next_payout_id.checked_add(1).unwrap_or(1);
 
+            let current_block = self.env().block_number();
             let mut approvals = Vec::with_capacity(MAX_APPROVALS);
             approvals.push(self.env().caller());
+            let mut approval_blocks = Vec::with_capacity(MAX_APPROVALS);
+            approval_blocks.push(current_block);
+
+            let deadline = if self.proposal_expiry_blocks == 0 {
+                0
+            } else {
+                current_block.saturating_add(self.proposal_expiry_blocks)
+            };
 
             let payout = Payout {
                 id: payout_id,
                 to,
                 amount,
-                block_number: self.env().block_number(),
+                block_number: current_block,
                 approvals,
+                approval_blocks,
+                deadline,
+                rejections: Vec::new(),
                 payout_type,
                 status: PayoutStatus::Pending,
                 interval_blocks,
@@ -346,17 +508,24 @@ pub mod treasuryflow {
             Ok(payout_id)
         }
 
-        /// Approve a pending payout
+        /// Approve a pending payout. Delegates approve for every treasurer
+        /// whose delegation to them is active.
         #[ink(message)]
         pub fn approve(&mut self, payout_id: u32) -> Result<()> {
-            if !self.treasurers.contains(&self.env().caller()) {
+            let caller = self.env().caller();
+            let current_block = self.env().block_number();
+            let approvers = self.approvers_for(caller, current_block);
+            if approvers.is_empty() {
                 return Err(Error::NotTreasurer);
             }
 
-            let caller = self.env().caller();
             if let Some(payout) = self.pending_payouts.iter_mut().find(|p| p.id == payout_id) {
-                if !payout.approvals.contains(&caller) && payout.approvals.len() < MAX_APPROVALS {
-                    payout.approvals.push(caller);
+                if Self::is_past_deadline(payout, current_block) {
+                    return Err(Error::PayoutExpired);
+                }
+                Self::ensure_approval_capacity(payout, &approvers)?;
+                for approver in approvers {
+                    Self::record_approval(payout, approver, current_block);
                 }
                 return Ok(());
             }
@@ -364,6 +533,413 @@ pub mod treasuryflow {
             Err(Error::PayoutNotFound)
         }
 
+        /// Delegate the caller's approval power to another address for a block range
+        #[ink(message)]
+        pub fn delegate_approval(
+            &mut self,
+            delegate: H160,
+            start_block: BlockNumber,
+            end_block: BlockNumber,
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            if !self.treasurers.contains(&caller) {
+                return Err(Error::NotTreasurer);
+            }
+            if delegate == caller {
+                return Err(Error::SelfDelegation);
+            }
+
+            let current_block = self.env().block_number();
+            if end_block < start_block || end_block < current_block {
+                return Err(Error::InvalidDelegationPeriod);
+            }
+
+            // Approval power can only be passed on once, for any block both delegations cover
+            let first_block = start_block.max(current_block);
+            let delegate_has_delegated = self
+                .delegations
+                .get(delegate)
+                .is_some_and(|d| Self::delegation_overlaps(&d, first_block, end_block));
+            let caller_is_delegate = self.treasurers.iter().any(|treasurer| {
+                self.delegations.get(treasurer).is_some_and(|d| {
+                    d.delegate == caller && Self::delegation_overlaps(&d, first_block, end_block)
+                })
+            });
+            if delegate_has_delegated || caller_is_delegate {
+                return Err(Error::DelegationChain);
+            }
+
+            self.delegations.insert(
+                caller,
+                &Delegation {
+                    delegate,
+                    start_block,
+                    end_block,
+                },
+            );
+            self.env().emit_event(ApprovalDelegated {
+                delegator: caller,
+                delegate,
+                start_block,
+                end_block,
+            });
+
+            Ok(())
+        }
+
+        /// Remove the caller's delegation
+        #[ink(message)]
+        pub fn undelegate_approval(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            let delegation = self.delegations.take(caller).ok_or(Error::NoDelegation)?;
+            self.env().emit_event(ApprovalUndelegated {
+                delegator: caller,
+                delegate: delegation.delegate,
+            });
+
+            Ok(())
+        }
+
+        /// Get the delegation of a treasurer
+        #[ink(message)]
+        pub fn get_delegation(&self, delegator: H160) -> Option<Delegation> {
+            self.delegations.get(delegator)
+        }
+
+        /// Whether a delegation is active in any block from `start_block` to `end_block`
+        fn delegation_overlaps(
+            delegation: &Delegation,
+            start_block: BlockNumber,
+            end_block: BlockNumber,
+        ) -> bool {
+            delegation.start_block <= end_block && start_block <= delegation.end_block
+        }
+
+        /// Treasurers an approval of `account` counts for: the account itself if it
+        /// is a treasurer plus every treasurer with an active delegation to it
+        fn approvers_for(&self, account: H160, current_block: BlockNumber) -> Vec<H160> {
+            let mut approvers = Vec::new();
+            if self.treasurers.contains(&account) {
+                approvers.push(account);
+            }
+            for treasurer in self.treasurers.iter() {
+                let delegated = self.delegations.get(treasurer).is_some_and(|d| {
+                    d.delegate == account
+                        && d.start_block <= current_block
+                        && current_block <= d.end_block
+                });
+                if delegated && *treasurer != account {
+                    approvers.push(*treasurer);
+                }
+            }
+            approvers
+        }
+
+        /// Record approvals signed off-chain by treasurers in a single transaction.
+        ///
+        /// Each signature is a 65 byte ECDSA signature over the hash returned by
+        /// `get_approval_message_hash`. All signers must be treasurers or active
+        /// delegates, otherwise nothing is recorded. Approvals that are already
+        /// recorded are left as they are, so submitting a signature again is a no-op
+        /// and relaying part of a batch early does not invalidate the rest. The nonce
+        /// only moves when a vote is withdrawn through `revoke_approval`, `reject` or
+        /// `remove_treasurer`.
+        #[ink(message)]
+        pub fn approve_with_signatures(
+            &mut self,
+            payout_id: u32,
+            signatures: Vec<[u8; 65]>,
+        ) -> Result<()> {
+            if signatures.is_empty() {
+                return Err(Error::InvalidSignature);
+            }
+
+            let nonce = self.approval_nonces.get(payout_id).unwrap_or(0);
+            let message_hash = self.approval_message_hash(payout_id, nonce);
+
+            // Recover and check every signer before touching the payout
+            let current_block = self.env().block_number();
+            let mut approvers = Vec::with_capacity(signatures.len());
+            for signature in signatures.iter() {
+                let pubkey = self
+                    .env()
+                    .ecdsa_recover(signature, &message_hash)
+                    .map_err(|_| Error::InvalidSignature)?;
+                let signer = H160::from(
+                    self.env()
+                        .ecdsa_to_eth_address(&pubkey)
+                        .map_err(|_| Error::InvalidSignature)?,
+                );
+                let signer_approvers = self.approvers_for(signer, current_block);
+                if signer_approvers.is_empty() {
+                    return Err(Error::NotTreasurer);
+                }
+                for approver in signer_approvers {
+                    if !approvers.contains(&approver) {
+                        approvers.push(approver);
+                    }
+                }
+            }
+
+            let payout = self
+                .pending_payouts
+                .iter_mut()
+                .find(|p| p.id == payout_id)
+                .ok_or(Error::PayoutNotFound)?;
+            if Self::is_past_deadline(payout, current_block) {
+                return Err(Error::PayoutExpired);
+            }
+            // Replayed signatures neither refresh nor duplicate an approval
+            approvers.retain(|approver| !payout.approvals.contains(approver));
+            if approvers.is_empty() {
+                return Ok(());
+            }
+            Self::ensure_approval_capacity(payout, &approvers)?;
+            for approver in approvers.iter() {
+                Self::record_approval(payout, *approver, current_block);
+            }
+
+            self.env().emit_event(SignedApprovalsRecorded {
+                payout_id,
+                approvers,
+                nonce,
+            });
+
+            Ok(())
+        }
+
+        /// Get the current approval nonce of a payout
+        #[ink(message)]
+        pub fn get_approval_nonce(&self, payout_id: u32) -> u32 {
+            self.approval_nonces.get(payout_id).unwrap_or(0)
+        }
+
+        /// Get the hash treasurers have to sign to approve a payout off-chain
+        #[ink(message)]
+        pub fn get_approval_message_hash(&self, payout_id: u32) -> [u8; 32] {
+            self.approval_message_hash(payout_id, self.get_approval_nonce(payout_id))
+        }
+
+        /// Invalidate the signed approvals collected for a payout so far
+        fn bump_approval_nonce(&mut self, payout_id: u32) -> Result<()> {
+            let nonce = self.approval_nonces.get(payout_id).unwrap_or(0);
+            // Wrapping around would make signatures over old nonces valid again
+            let next_nonce = nonce.checked_add(1).ok_or(Error::NonceOverflow)?;
+            self.approval_nonces.insert(payout_id, &next_nonce);
+            Ok(())
+        }
+
+        /// Keccak256 of the SCALE encoded (domain, contract address, payout id, nonce)
+        fn approval_message_hash(&self, payout_id: u32, nonce: u32) -> [u8; 32] {
+            self.env().hash_encoded::<Keccak256, _>(&(
+                APPROVAL_DOMAIN,
+                self.env().address(),
+                payout_id,
+                nonce,
+            ))
+        }
+
+        /// Withdraw an approval given earlier. Delegates withdraw the approvals of
+        /// every treasurer whose delegation to them is active.
+        #[ink(message)]
+        pub fn revoke_approval(&mut self, payout_id: u32) -> Result<()> {
+            let approvers = self.approvers_for(self.env().caller(), self.env().block_number());
+            if approvers.is_empty() {
+                return Err(Error::NotTreasurer);
+            }
+
+            let payout = self
+                .pending_payouts
+                .iter_mut()
+                .find(|p| p.id == payout_id)
+                .ok_or(Error::PayoutNotFound)?;
+            if payout.status != PayoutStatus::Pending {
+                return Err(Error::PayoutAlreadyActive);
+            }
+            if !approvers.iter().any(|approver| payout.approvals.contains(approver)) {
+                return Err(Error::NotApproved);
+            }
+            self.bump_approval_nonce(payout_id)?;
+
+            let payout = self
+                .pending_payouts
+                .iter_mut()
+                .find(|p| p.id == payout_id)
+                .ok_or(Error::PayoutNotFound)?;
+            let revoked: Vec<H160> = approvers
+                .into_iter()
+                .filter(|approver| Self::remove_approval(payout, *approver))
+                .collect();
+
+            for treasurer in revoked {
+                self.env().emit_event(ApprovalRevoked {
+                    payout_id,
+                    treasurer,
+                });
+            }
+
+            Ok(())
+        }
+
+        /// Vote against a pending payout. Any approval of the caller is withdrawn.
+        /// Once the remaining treasurers can no longer reach the threshold, the
+        /// payout is cancelled and moved to past payouts. Delegates reject for every
+        /// treasurer whose delegation to them is active.
+        #[ink(message)]
+        pub fn reject(&mut self, payout_id: u32) -> Result<()> {
+            let approvers = self.approvers_for(self.env().caller(), self.env().block_number());
+            if approvers.is_empty() {
+                return Err(Error::NotTreasurer);
+            }
+
+            let index = self
+                .pending_payouts
+                .iter()
+                .position(|p| p.id == payout_id)
+                .ok_or(Error::PayoutNotFound)?;
+            // Running streams can only be stopped through `cancel_payout`
+            if self.pending_payouts[index].status != PayoutStatus::Pending {
+                return Err(Error::PayoutAlreadyActive);
+            }
+
+            // Signatures collected before the rejection must not turn it back into an approval
+            self.bump_approval_nonce(payout_id)?;
+
+            // Get required approvals *before* mutable borrow
+            let required_approvals =
+                self.get_required_approvals(self.pending_payouts[index].amount) as usize;
+            let treasurer_count = self.treasurers.len();
+
+            let payout = &mut self.pending_payouts[index];
+            for approver in approvers {
+                Self::remove_approval(payout, approver);
+                if !payout.rejections.contains(&approver) {
+                    payout.rejections.push(approver);
+                }
+            }
+            let rejections = payout.rejections.len();
+
+            if treasurer_count.saturating_sub(rejections) < required_approvals {
+                let mut rejected_payout = self.pending_payouts.remove(index);
+                rejected_payout.status = PayoutStatus::Cancelled;
+                self.past_payouts
+                    .insert(rejected_payout.id, &rejected_payout);
+                self.past_payout_ids.push(rejected_payout.id);
+                self.env().emit_event(PayoutRejected {
+                    payout_id,
+                    rejections: u32::try_from(rejections).unwrap_or(u32::MAX),
+                });
+            }
+
+            Ok(())
+        }
+
+        /// Remove an approval, returns whether the approver had approved
+        fn remove_approval(payout: &mut Payout, approver: H160) -> bool {
+            match payout.approvals.iter().position(|a| *a == approver) {
+                Some(index) => {
+                    payout.approvals.remove(index);
+                    if index < payout.approval_blocks.len() {
+                        payout.approval_blocks.remove(index);
+                    }
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Fail if recording `approvers` would exceed `MAX_APPROVALS`
+        fn ensure_approval_capacity(payout: &Payout, approvers: &[H160]) -> Result<()> {
+            let new_approvals = approvers
+                .iter()
+                .filter(|approver| !payout.approvals.contains(approver))
+                .count();
+            if payout.approvals.len().saturating_add(new_approvals) > MAX_APPROVALS {
+                return Err(Error::ApprovalsFull);
+            }
+            Ok(())
+        }
+
+        /// Record an approval, or refresh the block of an existing one.
+        /// Approving withdraws an earlier rejection of the same treasurer.
+        fn record_approval(payout: &mut Payout, approver: H160, block: BlockNumber) {
+            payout.rejections.retain(|r| *r != approver);
+            match payout.approvals.iter().position(|a| *a == approver) {
+                Some(index) => {
+                    if let Some(approval_block) = payout.approval_blocks.get_mut(index) {
+                        *approval_block = block;
+                    }
+                }
+                None => {
+                    if payout.approvals.len() < MAX_APPROVALS {
+                        payout.approvals.push(approver);
+                        payout.approval_blocks.push(block);
+                    }
+                }
+            }
+        }
+
+        /// Whether a proposal that has not been paid yet is past its deadline
+        fn is_past_deadline(payout: &Payout, current_block: BlockNumber) -> bool {
+            payout.status == PayoutStatus::Pending
+                && payout.deadline != 0
+                && current_block > payout.deadline
+        }
+
+        /// Whether a proposal missed its threshold by its deadline. Such proposals are
+        /// archived as `Expired` by the next `process_pending_payouts`.
+        fn is_expired(&self, payout: &Payout, current_block: BlockNumber) -> bool {
+            Self::is_past_deadline(payout, current_block)
+                && self.counted_approvals(payout, current_block)
+                    < self.get_required_approvals(payout.amount) as usize
+        }
+
+        /// Number of approvals that still count towards the threshold.
+        /// Approvals of payouts that already started paying out never go stale.
+        fn counted_approvals(&self, payout: &Payout, current_block: BlockNumber) -> usize {
+            if payout.status != PayoutStatus::Pending || self.approval_max_age_blocks == 0 {
+                return payout.approvals.len();
+            }
+
+            payout
+                .approval_blocks
+                .iter()
+                .filter(|block| current_block <= block.saturating_add(self.approval_max_age_blocks))
+                .count()
+        }
+
+        /// Set the number of blocks new proposals have to reach their threshold in
+        #[ink(message)]
+        pub fn set_proposal_expiry(&mut self, blocks: BlockNumber) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            self.proposal_expiry_blocks = blocks;
+            Ok(())
+        }
+
+        /// Set the number of blocks after which an approval stops counting
+        #[ink(message)]
+        pub fn set_approval_max_age(&mut self, blocks: BlockNumber) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            self.approval_max_age_blocks = blocks;
+            Ok(())
+        }
+
+        /// Get the proposal expiry window in blocks
+        #[ink(message)]
+        pub fn get_proposal_expiry(&self) -> BlockNumber {
+            self.proposal_expiry_blocks
+        }
+
+        /// Get the maximum approval age in blocks
+        #[ink(message)]
+        pub fn get_approval_max_age(&self) -> BlockNumber {
+            self.approval_max_age_blocks
+        }
+
         /// Process pending payouts
         #[ink(message)]
         pub fn process_pending_payouts(&mut self) -> Result<()> {
@@ -397,9 +973,19 @@ pub mod treasuryflow {
                 //     required_approvals
                 // );
 
-                // Skip if not enough approvals
-                if payout.approvals.len() < required_approvals as usize {
-                    remaining_payouts.push(payout);
+                // Skip if not enough approvals, archive proposals past their deadline
+                if self.counted_approvals(&payout, current_block) < required_approvals as usize {
+                    if Self::is_past_deadline(&payout, current_block) {
+                        let mut expired_payout = payout;
+                        expired_payout.status = PayoutStatus::Expired;
+                        self.env().emit_event(PayoutExpired {
+                            payout_id: expired_payout.id,
+                            deadline: expired_payout.deadline,
+                        });
+                        processed_payouts.push(expired_payout);
+                    } else {
+                        remaining_payouts.push(payout);
+                    }
                     continue;
                 }
 
@@ -520,10 +1106,15 @@ pub mod treasuryflow {
             self.treasurers.iter().cloned().collect()
         }
 
-        /// Get the pending payouts
+        /// Get the pending payouts, leaving out proposals that already expired
         #[ink(message)]
         pub fn get_pending_payouts(&self) -> Vec<Payout> {
-            self.pending_payouts.clone()
+            let current_block = self.env().block_number();
+            self.pending_payouts
+                .iter()
+                .filter(|payout| !self.is_expired(payout, current_block))
+                .cloned()
+                .collect()
         }
 
         /// Cancel a payout (requires treasurer threshold approval)
@@ -608,6 +1199,47 @@ pub mod treasuryflow {
             self.past_payout_ids.clone()
         }
 
+        /// Export up to `limit` payouts starting at `offset`, pending payouts first
+        /// followed by past payouts in the order they were archived. Instances deployed
+        /// before this message existed are exported from raw storage by `treasury-v6-export`.
+        #[ink(message)]
+        pub fn export_payouts(&self, offset: u32, limit: u32) -> Vec<PayoutExport> {
+            let current_block = self.env().block_number();
+            self.pending_payouts
+                .iter()
+                .cloned()
+                .chain(
+                    self.past_payout_ids
+                        .iter()
+                        .filter_map(|id| self.past_payouts.get(id)),
+                )
+                .skip(offset as usize)
+                .take(limit as usize)
+                .map(|payout| {
+                    let status = if self.is_expired(&payout, current_block) {
+                        PayoutStatus::Expired
+                    } else {
+                        payout.status.clone()
+                    };
+                    let approvals = self.counted_approvals(&payout, current_block);
+                    PayoutExport {
+                        id: payout.id,
+                        to: payout.to,
+                        amount: payout.amount,
+                        payout_type: payout.payout_type,
+                        status,
+                        block_number: payout.block_number,
+                        interval_blocks: payout.interval_blocks,
+                        total_payouts: payout.total_payouts,
+                        completed_payouts: payout.completed_payouts,
+                        cliff_blocks: payout.cliff_blocks,
+                        approvals: u32::try_from(approvals).unwrap_or(u32::MAX),
+                        required_approvals: self.get_required_approvals(payout.amount),
+                    }
+                })
+                .collect()
+        }
+
         /// Get required approvals for an amount
         #[ink(message)]
         pub fn get_required_approvals(&self, amount: U256) -> u32 {
@@ -637,6 +1269,32 @@ pub mod treasuryflow {
             treasury
         }
 
+        /// Helper to derive a treasurer address from a known test key
+        fn test_key(seed: u8) -> (secp256k1::SecretKey, H160) {
+            let secret = secp256k1::SecretKey::from_byte_array(&[seed; 32]).unwrap();
+            let public = secp256k1::PublicKey::from_secret_key(secp256k1::SECP256K1, &secret);
+            let mut address = [0u8; 20];
+            ink::env::ecdsa_to_eth_address(&public.serialize(), &mut address).unwrap();
+            (secret, H160::from(address))
+        }
+
+        /// Helper to sign the current approval payload of a payout
+        fn sign_approval(
+            treasury: &Treasury,
+            secret: &secp256k1::SecretKey,
+            payout_id: u32,
+        ) -> [u8; 65] {
+            let message =
+                secp256k1::Message::from_digest(treasury.get_approval_message_hash(payout_id));
+            let (recovery_id, compact) = secp256k1::SECP256K1
+                .sign_ecdsa_recoverable(&message, secret)
+                .serialize_compact();
+            let mut signature = [0u8; 65];
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = i32::from(recovery_id) as u8;
+            signature
+        }
+
         /// Test constructor
         #[ink::test]
         fn setup_works() {
@@ -821,5 +1479,621 @@ pub mod treasuryflow {
                 Ok(payout_amount)
             );
         }
+
+        /// Test approvals collected off-chain and submitted in one call
+        #[ink::test]
+        fn signed_approvals_work() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            let payout_amount: U256 = 4_000_000_000_000_u128.into();
+            test::set_account_balance(test::callee(), payout_amount);
+
+            let (key1, signer1) = test_key(1);
+            let (key2, signer2) = test_key(2);
+            treasury.add_treasurer(signer1).unwrap();
+            treasury.add_treasurer(signer2).unwrap();
+            test::set_account_balance(accounts.eve, U256::from(0));
+
+            // Bob proposes a payout that needs 3 approvals
+            test::set_caller(accounts.bob);
+            let payout_id = treasury.add_payout(accounts.eve, payout_amount).unwrap();
+            assert_eq!(treasury.get_approval_nonce(payout_id), 0);
+
+            // Anyone can relay the signatures, including a duplicate signature
+            let signatures = vec![
+                sign_approval(&treasury, &key1, payout_id),
+                sign_approval(&treasury, &key2, payout_id),
+                sign_approval(&treasury, &key1, payout_id),
+            ];
+            test::set_caller(accounts.frank);
+            treasury
+                .approve_with_signatures(payout_id, signatures)
+                .unwrap();
+
+            let payout = &treasury.get_pending_payouts()[0];
+            assert_eq!(payout.approvals, vec![accounts.bob, signer1, signer2]);
+            assert_eq!(treasury.get_approval_nonce(payout_id), 0);
+
+            treasury.process_pending_payouts().unwrap();
+            assert_eq!(
+                test::get_account_balance::<DefaultEnvironment>(accounts.eve),
+                Ok(payout_amount)
+            );
+        }
+
+        /// Test that signed approvals cannot be replayed
+        #[ink::test]
+        fn signed_approvals_replay_rejected() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            let (key1, signer1) = test_key(1);
+            treasury.add_treasurer(signer1).unwrap();
+
+            test::set_caller(accounts.bob);
+            let payout_id = treasury.add_payout(accounts.eve, U256::from(999)).unwrap();
+            let signature = sign_approval(&treasury, &key1, payout_id);
+            treasury
+                .approve_with_signatures(payout_id, vec![signature])
+                .unwrap();
+
+            // Submitting it again neither refreshes nor duplicates the approval
+            test::set_block_number::<DefaultEnvironment>(5);
+            treasury
+                .approve_with_signatures(payout_id, vec![signature])
+                .unwrap();
+            assert_eq!(
+                treasury.get_pending_payouts()[0].approval_blocks,
+                vec![0, 0]
+            );
+
+            // Once the approval is revoked the nonce moves on and the signature
+            // recovers to a different signer
+            test::set_caller(signer1);
+            treasury.revoke_approval(payout_id).unwrap();
+            assert_eq!(treasury.get_approval_nonce(payout_id), 1);
+            test::set_caller(accounts.bob);
+            assert_eq!(
+                treasury.approve_with_signatures(payout_id, vec![signature]),
+                Err(Error::NotTreasurer)
+            );
+
+            // A signature for one payout is not valid for another
+            let other_id = treasury.add_payout(accounts.eve, U256::from(999)).unwrap();
+            assert_eq!(
+                treasury.approve_with_signatures(other_id, vec![signature]),
+                Err(Error::NotTreasurer)
+            );
+            assert_eq!(
+                treasury.approve_with_signatures(other_id, Vec::new()),
+                Err(Error::InvalidSignature)
+            );
+
+            // An exhausted nonce is not wrapped back to signatures already used
+            treasury.approval_nonces.insert(other_id, &u32::MAX);
+            assert_eq!(treasury.revoke_approval(other_id), Err(Error::NonceOverflow));
+            assert_eq!(treasury.get_approval_nonce(other_id), u32::MAX);
+            assert_eq!(
+                treasury.get_pending_payouts()[1].approvals,
+                vec![accounts.bob]
+            );
+        }
+
+        /// Test that relaying part of a batch early does not invalidate the rest
+        #[ink::test]
+        fn signed_approvals_survive_front_running() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            let payout_amount: U256 = 4_000_000_000_000_u128.into();
+            let (key1, signer1) = test_key(1);
+            let (key2, signer2) = test_key(2);
+            treasury.add_treasurer(signer1).unwrap();
+            treasury.add_treasurer(signer2).unwrap();
+
+            test::set_caller(accounts.bob);
+            let payout_id = treasury.add_payout(accounts.eve, payout_amount).unwrap();
+            let signatures = vec![
+                sign_approval(&treasury, &key1, payout_id),
+                sign_approval(&treasury, &key2, payout_id),
+            ];
+
+            // Someone holding the first signature submits it on its own
+            test::set_caller(accounts.frank);
+            treasury
+                .approve_with_signatures(payout_id, vec![signatures[0]])
+                .unwrap();
+
+            // The full batch still goes through and only reports the new approval
+            treasury
+                .approve_with_signatures(payout_id, signatures)
+                .unwrap();
+            assert_eq!(
+                treasury.get_pending_payouts()[0].approvals,
+                vec![accounts.bob, signer1, signer2]
+            );
+            let emitted_events = test::recorded_events().collect::<Vec<_>>();
+            let recorded_event = <SignedApprovalsRecorded as parity_scale_codec::Decode>::decode(
+                &mut &emitted_events.last().unwrap().data[..],
+            )
+            .expect("Failed to decode SignedApprovalsRecorded event");
+            assert_eq!(recorded_event.approvers, vec![signer2]);
+            assert_eq!(recorded_event.nonce, 0);
+        }
+
+        /// Test that approvals beyond `MAX_APPROVALS` are rejected, not dropped
+        #[ink::test]
+        fn signed_approvals_respect_capacity() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            let keys: Vec<_> = (1..=MAX_APPROVALS as u8).map(test_key).collect();
+            for (_, signer) in keys.iter() {
+                treasury.add_treasurer(*signer).unwrap();
+            }
+
+            // Bob's approval plus 10 signed ones do not fit
+            test::set_caller(accounts.bob);
+            let payout_id = treasury.add_payout(accounts.eve, U256::from(999)).unwrap();
+            let signatures: Vec<_> = keys
+                .iter()
+                .map(|(key, _)| sign_approval(&treasury, key, payout_id))
+                .collect();
+            assert_eq!(
+                treasury.approve_with_signatures(payout_id, signatures.clone()),
+                Err(Error::ApprovalsFull)
+            );
+            assert_eq!(
+                treasury.get_pending_payouts()[0].approvals,
+                vec![accounts.bob]
+            );
+
+            // Nine of them fill the list, after which direct approvals fail as well
+            treasury
+                .approve_with_signatures(payout_id, signatures[..9].to_vec())
+                .unwrap();
+            assert_eq!(
+                treasury.get_pending_payouts()[0].approvals.len(),
+                MAX_APPROVALS
+            );
+            test::set_caller(keys[9].1);
+            assert_eq!(treasury.approve(payout_id), Err(Error::ApprovalsFull));
+        }
+
+        /// Test that a single non-treasurer signature rejects the whole batch
+        #[ink::test]
+        fn signed_approvals_are_atomic() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            let (key1, signer1) = test_key(1);
+            let (outsider_key, _) = test_key(9);
+            treasury.add_treasurer(signer1).unwrap();
+
+            test::set_caller(accounts.bob);
+            let payout_id = treasury.add_payout(accounts.eve, U256::from(999)).unwrap();
+            let signatures = vec![
+                sign_approval(&treasury, &key1, payout_id),
+                sign_approval(&treasury, &outsider_key, payout_id),
+            ];
+
+            assert_eq!(
+                treasury.approve_with_signatures(payout_id, signatures),
+                Err(Error::NotTreasurer)
+            );
+            assert_eq!(
+                treasury.get_pending_payouts()[0].approvals,
+                vec![accounts.bob]
+            );
+            assert_eq!(treasury.get_approval_nonce(payout_id), 0);
+
+            // Signatures for unknown payouts are rejected as well
+            let signature = sign_approval(&treasury, &key1, 42);
+            assert_eq!(
+                treasury.approve_with_signatures(42, vec![signature]),
+                Err(Error::PayoutNotFound)
+            );
+        }
+
+        /// Test that proposals missing their threshold by the deadline expire
+        #[ink::test]
+        fn proposal_expiry_works() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            treasury.add_treasurer(accounts.charlie).unwrap();
+            treasury.set_proposal_expiry(10).unwrap();
+
+            // Medium payout requires 2 approvals, only Bob approves
+            test::set_caller(accounts.bob);
+            let expiring_id = treasury
+                .add_payout(accounts.eve, U256::from(1_000_000_000_000_u128))
+                .unwrap();
+            let small_id = treasury.add_payout(accounts.eve, U256::from(999)).unwrap();
+
+            test::set_block_number::<DefaultEnvironment>(11);
+            test::set_caller(accounts.charlie);
+            assert_eq!(treasury.approve(expiring_id), Err(Error::PayoutExpired));
+
+            // Reads stop reporting the proposal before processing archives it
+            let pending = treasury.get_pending_payouts();
+            assert_eq!(pending.len(), 1);
+            assert_eq!(pending[0].id, small_id);
+            assert_eq!(treasury.export_payouts(0, 10)[0].status, PayoutStatus::Expired);
+
+            treasury.process_pending_payouts().unwrap();
+            assert!(treasury.get_pending_payouts().is_empty());
+            assert_eq!(treasury.get_past_payout_ids(), vec![expiring_id, small_id]);
+            assert_eq!(
+                treasury.past_payouts.get(expiring_id).unwrap().status,
+                PayoutStatus::Expired
+            );
+            assert_eq!(
+                treasury.past_payouts.get(small_id).unwrap().status,
+                PayoutStatus::Completed
+            );
+
+            // The expiry is the last event, processing emits nothing else here
+            let emitted_events = test::recorded_events().collect::<Vec<_>>();
+            let expired_event = <PayoutExpired as parity_scale_codec::Decode>::decode(
+                &mut &emitted_events.last().unwrap().data[..],
+            )
+            .expect("Failed to decode PayoutExpired event");
+            assert_eq!(expired_event.payout_id, expiring_id);
+            assert_eq!(expired_event.deadline, 10);
+        }
+
+        /// Test that approvals older than the configured age stop counting
+        #[ink::test]
+        fn stale_approvals_stop_counting() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            treasury.add_treasurer(accounts.charlie).unwrap();
+            treasury.set_approval_max_age(5).unwrap();
+            let payout_amount = U256::from(1_000_000_000_000_u128);
+            test::set_account_balance(test::callee(), payout_amount);
+            test::set_account_balance(accounts.eve, U256::from(0));
+
+            test::set_caller(accounts.bob);
+            let payout_id = treasury.add_payout(accounts.eve, payout_amount).unwrap();
+
+            // Bob's approval from block 0 is stale by the time Charlie approves
+            test::set_block_number::<DefaultEnvironment>(10);
+            test::set_caller(accounts.charlie);
+            treasury.approve(payout_id).unwrap();
+            treasury.process_pending_payouts().unwrap();
+            assert_eq!(treasury.get_pending_payouts().len(), 1);
+
+            // Refreshing Bob's approval makes the payout go through
+            test::set_caller(accounts.bob);
+            treasury.approve(payout_id).unwrap();
+            assert_eq!(
+                treasury.get_pending_payouts()[0].approval_blocks,
+                vec![10, 10]
+            );
+            treasury.process_pending_payouts().unwrap();
+            assert!(treasury.get_pending_payouts().is_empty());
+            assert_eq!(
+                test::get_account_balance::<DefaultEnvironment>(accounts.eve),
+                Ok(payout_amount)
+            );
+
+            test::set_caller(accounts.django);
+            assert_eq!(treasury.set_approval_max_age(0), Err(Error::NotOwner));
+        }
+
+        /// Test withdrawing an approval
+        #[ink::test]
+        fn revoke_approval_works() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            treasury.add_treasurer(accounts.charlie).unwrap();
+
+            test::set_caller(accounts.bob);
+            let payout_id = treasury
+                .add_payout(accounts.eve, U256::from(1_000_000_000_000_u128))
+                .unwrap();
+
+            test::set_caller(accounts.charlie);
+            assert_eq!(treasury.revoke_approval(payout_id), Err(Error::NotApproved));
+            treasury.approve(payout_id).unwrap();
+            treasury.revoke_approval(payout_id).unwrap();
+            assert_eq!(
+                treasury.get_pending_payouts()[0].approvals,
+                vec![accounts.bob]
+            );
+            assert_eq!(treasury.get_pending_payouts()[0].approval_blocks.len(), 1);
+
+            // Without Charlie's approval the payout stays pending
+            treasury.process_pending_payouts().unwrap();
+            assert_eq!(treasury.get_pending_payouts().len(), 1);
+
+            test::set_caller(accounts.django);
+            assert_eq!(
+                treasury.revoke_approval(payout_id),
+                Err(Error::NotTreasurer)
+            );
+            test::set_caller(accounts.bob);
+            assert_eq!(treasury.revoke_approval(42), Err(Error::PayoutNotFound));
+        }
+
+        /// Test that rejections cancel a payout once the threshold is out of reach
+        #[ink::test]
+        fn rejections_cancel_payout() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            treasury.add_treasurer(accounts.charlie).unwrap();
+            treasury.add_treasurer(accounts.django).unwrap();
+
+            // Medium payout requires 2 of 3 treasurers
+            test::set_caller(accounts.bob);
+            let payout_id = treasury
+                .add_payout(accounts.eve, U256::from(1_000_000_000_000_u128))
+                .unwrap();
+
+            // One rejection still leaves 2 possible approvals
+            test::set_caller(accounts.charlie);
+            treasury.reject(payout_id).unwrap();
+            treasury.reject(payout_id).unwrap();
+            assert_eq!(
+                treasury.get_pending_payouts()[0].rejections,
+                vec![accounts.charlie]
+            );
+
+            // Changing one's mind withdraws the rejection
+            treasury.approve(payout_id).unwrap();
+            assert!(treasury.get_pending_payouts()[0].rejections.is_empty());
+            treasury.reject(payout_id).unwrap();
+            assert_eq!(
+                treasury.get_pending_payouts()[0].approvals,
+                vec![accounts.bob]
+            );
+
+            // Bob rejecting his own proposal makes 2 approvals impossible
+            test::set_caller(accounts.bob);
+            treasury.reject(payout_id).unwrap();
+            assert!(treasury.get_pending_payouts().is_empty());
+            assert_eq!(treasury.get_past_payout_ids(), vec![payout_id]);
+            assert_eq!(
+                treasury.past_payouts.get(payout_id).unwrap().status,
+                PayoutStatus::Cancelled
+            );
+
+            let emitted_events = test::recorded_events().collect::<Vec<_>>();
+            let rejected_event = <PayoutRejected as parity_scale_codec::Decode>::decode(
+                &mut &emitted_events.last().unwrap().data[..],
+            )
+            .expect("Failed to decode PayoutRejected event");
+            assert_eq!(rejected_event.payout_id, payout_id);
+            assert_eq!(rejected_event.rejections, 2);
+
+            assert_eq!(treasury.reject(payout_id), Err(Error::PayoutNotFound));
+        }
+
+        /// Test that votes only apply to proposals and leave with removed treasurers
+        #[ink::test]
+        fn votes_limited_to_open_proposals() {
+            let owner = ink::env::caller();
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            treasury.add_treasurer(accounts.charlie).unwrap();
+            treasury.add_treasurer(accounts.django).unwrap();
+
+            // A small recurring payout starts paying with Bob's approval alone
+            test::set_caller(accounts.bob);
+            let stream_id = treasury
+                .add_recurring_payout(accounts.eve, U256::from(100), 10, 3)
+                .unwrap();
+            treasury.process_pending_payouts().unwrap();
+            assert_eq!(
+                treasury.get_pending_payouts()[0].status,
+                PayoutStatus::Active
+            );
+            assert_eq!(treasury.reject(stream_id), Err(Error::PayoutAlreadyActive));
+            assert_eq!(
+                treasury.revoke_approval(stream_id),
+                Err(Error::PayoutAlreadyActive)
+            );
+
+            // Charlie's rejection stops counting once Charlie is removed
+            let proposal_id = treasury
+                .add_payout(accounts.eve, U256::from(1_000_000_000_000_u128))
+                .unwrap();
+            test::set_caller(accounts.charlie);
+            treasury.reject(proposal_id).unwrap();
+            test::set_caller(owner);
+            treasury.remove_treasurer(accounts.charlie).unwrap();
+            let proposal = treasury
+                .get_pending_payouts()
+                .into_iter()
+                .find(|p| p.id == proposal_id)
+                .unwrap();
+            assert!(proposal.rejections.is_empty());
+            assert_eq!(proposal.approvals, vec![accounts.bob]);
+
+            // With 2 of 2 required, one rejection now cancels the proposal
+            test::set_caller(accounts.django);
+            treasury.reject(proposal_id).unwrap();
+            assert!(treasury.get_past_payout_ids().contains(&proposal_id));
+        }
+
+        /// Test approving on behalf of an absent treasurer
+        #[ink::test]
+        fn delegated_approvals_work() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            treasury.add_treasurer(accounts.charlie).unwrap();
+            let payout_amount = U256::from(1_000_000_000_000_u128);
+            test::set_account_balance(test::callee(), payout_amount);
+            test::set_account_balance(accounts.eve, U256::from(0));
+
+            // Charlie goes on leave and lets Frank approve for blocks 5 to 10
+            test::set_caller(accounts.charlie);
+            treasury.delegate_approval(accounts.frank, 5, 10).unwrap();
+            assert_eq!(
+                treasury.get_delegation(accounts.charlie),
+                Some(Delegation {
+                    delegate: accounts.frank,
+                    start_block: 5,
+                    end_block: 10,
+                })
+            );
+
+            test::set_caller(accounts.bob);
+            let payout_id = treasury.add_payout(accounts.eve, payout_amount).unwrap();
+
+            // Not active yet
+            test::set_caller(accounts.frank);
+            assert_eq!(treasury.approve(payout_id), Err(Error::NotTreasurer));
+
+            // Frank's approval counts for Charlie, Charlie approving again does not double count
+            test::set_block_number::<DefaultEnvironment>(5);
+            treasury.approve(payout_id).unwrap();
+            test::set_caller(accounts.charlie);
+            treasury.approve(payout_id).unwrap();
+            assert_eq!(
+                treasury.get_pending_payouts()[0].approvals,
+                vec![accounts.bob, accounts.charlie]
+            );
+
+            treasury.process_pending_payouts().unwrap();
+            assert_eq!(
+                test::get_account_balance::<DefaultEnvironment>(accounts.eve),
+                Ok(payout_amount)
+            );
+
+            // Delegates can also take an approval back or reject for the delegator
+            test::set_caller(accounts.bob);
+            let next_id = treasury.add_payout(accounts.eve, payout_amount).unwrap();
+            test::set_caller(accounts.frank);
+            treasury.approve(next_id).unwrap();
+            treasury.revoke_approval(next_id).unwrap();
+            assert_eq!(
+                treasury.get_pending_payouts()[0].approvals,
+                vec![accounts.bob]
+            );
+            treasury.reject(next_id).unwrap();
+            assert_eq!(
+                treasury.past_payouts.get(next_id).unwrap().rejections,
+                vec![accounts.charlie]
+            );
+
+            // The delegation ends after its block range
+            test::set_block_number::<DefaultEnvironment>(11);
+            assert_eq!(treasury.approve(next_id), Err(Error::NotTreasurer));
+            assert_eq!(treasury.reject(next_id), Err(Error::NotTreasurer));
+        }
+
+        /// Test delegation validation and undelegating
+        #[ink::test]
+        fn delegation_rules_work() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            treasury.add_treasurer(accounts.charlie).unwrap();
+            treasury.add_treasurer(accounts.django).unwrap();
+            test::set_block_number::<DefaultEnvironment>(20);
+
+            test::set_caller(accounts.frank);
+            assert_eq!(
+                treasury.delegate_approval(accounts.eve, 20, 30),
+                Err(Error::NotTreasurer)
+            );
+
+            test::set_caller(accounts.bob);
+            assert_eq!(
+                treasury.delegate_approval(accounts.bob, 20, 30),
+                Err(Error::SelfDelegation)
+            );
+            assert_eq!(
+                treasury.delegate_approval(accounts.eve, 30, 20),
+                Err(Error::InvalidDelegationPeriod)
+            );
+            assert_eq!(
+                treasury.delegate_approval(accounts.eve, 10, 19),
+                Err(Error::InvalidDelegationPeriod)
+            );
+
+            // Bob -> Charlie rules out Charlie -> Eve and Django -> Bob style chains
+            treasury
+                .delegate_approval(accounts.charlie, 20, 30)
+                .unwrap();
+            test::set_caller(accounts.charlie);
+            assert_eq!(
+                treasury.delegate_approval(accounts.eve, 20, 30),
+                Err(Error::DelegationChain)
+            );
+            test::set_caller(accounts.django);
+            assert_eq!(
+                treasury.delegate_approval(accounts.bob, 20, 30),
+                Err(Error::DelegationChain)
+            );
+            // Only ranges overlapping Bob's delegation form a chain
+            test::set_caller(accounts.charlie);
+            assert_eq!(
+                treasury.delegate_approval(accounts.eve, 25, 40),
+                Err(Error::DelegationChain)
+            );
+            treasury.delegate_approval(accounts.eve, 31, 40).unwrap();
+            treasury.undelegate_approval().unwrap();
+
+            // Charlie approving as treasurer and delegate records both approvals once
+            test::set_caller(accounts.django);
+            let payout_id = treasury.add_payout(accounts.eve, U256::from(999)).unwrap();
+            test::set_caller(accounts.charlie);
+            treasury.approve(payout_id).unwrap();
+            treasury.approve(payout_id).unwrap();
+            assert_eq!(
+                treasury.get_pending_payouts()[0].approvals,
+                vec![accounts.django, accounts.charlie, accounts.bob]
+            );
+
+            test::set_caller(accounts.bob);
+            treasury.undelegate_approval().unwrap();
+            assert_eq!(treasury.get_delegation(accounts.bob), None);
+            assert_eq!(treasury.undelegate_approval(), Err(Error::NoDelegation));
+
+            let emitted_events = test::recorded_events().collect::<Vec<_>>();
+            let undelegated_event = <ApprovalUndelegated as parity_scale_codec::Decode>::decode(
+                &mut &emitted_events.last().unwrap().data[..],
+            )
+            .expect("Failed to decode ApprovalUndelegated event");
+            assert_eq!(undelegated_event.delegator, accounts.bob);
+            assert_eq!(undelegated_event.delegate, accounts.charlie);
+
+            // Once the chain is gone Django may delegate to Bob
+            test::set_caller(accounts.django);
+            treasury.delegate_approval(accounts.bob, 20, 30).unwrap();
+        }
+
+        /// Test exporting pending and past payouts
+        #[ink::test]
+        fn export_payouts_works() {
+            let mut treasury = setup();
+            let accounts: test::DefaultAccounts = test::default_accounts();
+            test::set_caller(accounts.bob);
+            test::set_block_number::<DefaultEnvironment>(100);
+
+            let one_time_id = treasury
+                .add_payout(accounts.charlie, U256::from(100))
+                .unwrap();
+            let recurring_id = treasury
+                .add_recurring_payout(accounts.django, U256::from(50), 10, 3)
+                .unwrap();
+            treasury.process_pending_payouts().unwrap();
+
+            // The recurring payout is still pending after its first installment
+            let exported = treasury.export_payouts(0, 10);
+            assert_eq!(exported.len(), 2);
+            assert_eq!(exported[0].id, recurring_id);
+            assert_eq!(exported[0].payout_type, PayoutType::Recurring);
+            assert_eq!(exported[0].status, PayoutStatus::Active);
+            assert_eq!(exported[0].completed_payouts, 1);
+            assert_eq!(exported[0].total_payouts, 3);
+            assert_eq!(exported[0].block_number, 100);
+            assert_eq!(exported[0].approvals, 1);
+            assert_eq!(exported[0].required_approvals, 1);
+            assert_eq!(exported[1].id, one_time_id);
+            assert_eq!(exported[1].status, PayoutStatus::Completed);
+            assert_eq!(exported[1].amount, U256::from(100));
+
+            // Paging
+            assert_eq!(treasury.export_payouts(1, 1), vec![exported[1].clone()]);
+            assert!(treasury.export_payouts(2, 10).is_empty());
+        }
     }
 }
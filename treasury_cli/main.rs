@@ -0,0 +1,98 @@
+//! Operator CLI turning a CSV or TOML payroll into `add_payouts` call data for the treasury.
+
+mod payroll;
+
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+
+use ink::U256;
+use treasury::treasury::AssetMetadata;
+
+const USAGE: &str = "\
+Usage: treasury-payroll <payroll.csv|payroll.toml> [options]
+
+Validates every payout with the rules of `add_payouts` and prints the call data as hex.
+
+Options:
+  --asset <token>=<decimals>:<min_amount>:<precision_factor>
+                  Precision rules of a token the payroll pays in, as registered on the treasury
+  --out <file>    Write the raw call data to <file> instead of printing it";
+
+/// Command line options
+struct Options {
+    payroll: String,
+    token_rules: BTreeMap<ink::H160, AssetMetadata>,
+    out: Option<String>,
+}
+
+/// Helper function to parse `<token>=<decimals>:<min_amount>:<precision_factor>`
+fn parse_token_rules(value: &str) -> Option<(ink::H160, AssetMetadata)> {
+    let (token, rules) = value.split_once('=')?;
+    let mut parts = rules.split(':');
+    let metadata = AssetMetadata {
+        decimals: parts.next()?.parse().ok()?,
+        min_amount: U256::from_dec_str(parts.next()?).ok()?,
+        precision_factor: U256::from_dec_str(parts.next()?).ok()?,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((payroll::parse_address(token)?, metadata))
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut payroll = None;
+    let mut token_rules = BTreeMap::new();
+    let mut out = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--asset" => {
+                let value = args.next().ok_or("--asset needs a value")?;
+                let (token, metadata) = parse_token_rules(&value)
+                    .ok_or_else(|| format!("invalid --asset value `{value}`"))?;
+                token_rules.insert(token, metadata);
+            }
+            "--out" => out = Some(args.next().ok_or("--out needs a value")?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if payroll.is_none() && !arg.starts_with("--") => payroll = Some(arg),
+            _ => return Err(format!("unexpected argument `{arg}`\n\n{USAGE}")),
+        }
+    }
+    Ok(Options {
+        payroll: payroll.ok_or(USAGE)?,
+        token_rules,
+        out,
+    })
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let text = std::fs::read_to_string(&options.payroll)
+        .map_err(|error| format!("cannot read {}: {error}", options.payroll))?;
+    let rows = if options.payroll.ends_with(".toml") {
+        payroll::parse_toml(&text)
+    } else {
+        payroll::parse_csv(&text)
+    }
+    .map_err(|error| error.to_string())?;
+    let requests =
+        payroll::to_requests(&rows, &options.token_rules).map_err(|error| error.to_string())?;
+    let call_data = payroll::add_payouts_call_data(&requests);
+
+    match options.out {
+        Some(path) => std::fs::write(&path, &call_data)
+            .map_err(|error| format!("cannot write {path}: {error}"))?,
+        None => println!("0x{}", hex::encode(&call_data)),
+    }
+    eprintln!("{} payouts validated", requests.len());
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match parse_options(std::env::args().skip(1)).and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
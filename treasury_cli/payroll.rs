@@ -0,0 +1,437 @@
+//! Parsing and validation of payroll files into `add_payouts` requests.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ink::{H160, U256};
+use parity_scale_codec::Encode;
+use serde::Deserialize;
+use treasury::treasury::{
+    Asset, AssetMetadata, Error, OneTimeData, PayoutRequest, RecurringData, Treasury, VestedData,
+    NATIVE_ASSET_METADATA,
+};
+
+/// Columns of a CSV payroll, also the keys of a `[[payout]]` table in a TOML payroll
+pub const COLUMNS: [&str; 11] = [
+    "type",
+    "recipient",
+    "amount",
+    "asset",
+    "scheduled_block",
+    "start_block",
+    "interval_blocks",
+    "total_payments",
+    "cliff_block",
+    "vesting_duration_blocks",
+    "vesting_interval_blocks",
+];
+
+/// Amount in asset base units, TOML integers only fit small amounts so strings are accepted too
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Amount {
+    Integer(u64),
+    Text(String),
+}
+
+/// One payout of a payroll file, before validation
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PayrollRow {
+    #[serde(rename = "type")]
+    pub kind: String, // `one_time`, `recurring` or `vested`
+    pub recipient: String,
+    pub amount: Amount, // Per payment for recurring payouts, total for vested payouts
+    pub asset: Option<String>, // Token address, native currency when missing
+    pub scheduled_block: Option<u32>,
+    pub start_block: Option<u32>,
+    pub interval_blocks: Option<u32>,
+    pub total_payments: Option<u32>,
+    pub cliff_block: Option<u32>,
+    pub vesting_duration_blocks: Option<u32>,
+    pub vesting_interval_blocks: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct PayrollFile {
+    #[serde(default)]
+    payout: Vec<PayrollRow>,
+}
+
+/// Errors while reading a payroll. Rows are 1-based and count payouts, not file lines.
+#[derive(Debug, PartialEq)]
+pub enum PayrollError {
+    /// The file isn't a well-formed payroll
+    Parse(String),
+    /// A value doesn't parse as the type of its field
+    InvalidField { row: usize, field: &'static str },
+    /// A field required by the payout type is missing
+    MissingField { row: usize, field: &'static str },
+    /// The payout type isn't one of `one_time`, `recurring` or `vested`
+    UnknownType { row: usize, kind: String },
+    /// A token payout without precision rules for the token
+    UnknownAsset { row: usize, token: H160 },
+    /// The contract would reject the payout
+    Rejected { row: usize, error: Error },
+}
+
+impl fmt::Display for PayrollError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(message) => write!(f, "invalid payroll: {message}"),
+            Self::InvalidField { row, field } => write!(f, "row {row}: invalid `{field}`"),
+            Self::MissingField { row, field } => write!(f, "row {row}: missing `{field}`"),
+            Self::UnknownType { row, kind } => write!(f, "row {row}: unknown payout type `{kind}`"),
+            Self::UnknownAsset { row, token } => {
+                write!(f, "row {row}: no precision rules for token {token:?}")
+            }
+            Self::Rejected { row, error } => write!(f, "row {row}: rejected with {error:?}"),
+        }
+    }
+}
+
+/// Parse a CSV payroll with a header row naming its columns. Blank lines and lines
+/// starting with `#` are skipped, empty cells leave optional fields unset. Quoting isn't supported.
+pub fn parse_csv(text: &str) -> Result<Vec<PayrollRow>, PayrollError> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or_else(|| PayrollError::Parse("missing header row".into()))?
+        .split(',')
+        .map(str::trim)
+        .collect();
+    if let Some(column) = header.iter().find(|column| !COLUMNS.contains(column)) {
+        return Err(PayrollError::Parse(format!("unknown column `{column}`")));
+    }
+
+    lines
+        .enumerate()
+        .map(|(index, line)| {
+            let row = index + 1;
+            let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+            if cells.len() != header.len() {
+                return Err(PayrollError::Parse(format!(
+                    "row {row} has {} cells, the header has {}",
+                    cells.len(),
+                    header.len()
+                )));
+            }
+            let cell = |column: &str| {
+                header
+                    .iter()
+                    .position(|name| *name == column)
+                    .map(|position| cells[position])
+                    .filter(|value| !value.is_empty())
+            };
+            let number = |field: &'static str| {
+                cell(field)
+                    .map(|value| {
+                        value
+                            .parse::<u32>()
+                            .map_err(|_| PayrollError::InvalidField { row, field })
+                    })
+                    .transpose()
+            };
+
+            Ok(PayrollRow {
+                kind: cell("type")
+                    .ok_or(PayrollError::MissingField { row, field: "type" })?
+                    .to_string(),
+                recipient: cell("recipient")
+                    .ok_or(PayrollError::MissingField {
+                        row,
+                        field: "recipient",
+                    })?
+                    .to_string(),
+                amount: Amount::Text(
+                    cell("amount")
+                        .ok_or(PayrollError::MissingField {
+                            row,
+                            field: "amount",
+                        })?
+                        .to_string(),
+                ),
+                asset: cell("asset").map(str::to_string),
+                scheduled_block: number("scheduled_block")?,
+                start_block: number("start_block")?,
+                interval_blocks: number("interval_blocks")?,
+                total_payments: number("total_payments")?,
+                cliff_block: number("cliff_block")?,
+                vesting_duration_blocks: number("vesting_duration_blocks")?,
+                vesting_interval_blocks: number("vesting_interval_blocks")?,
+            })
+        })
+        .collect()
+}
+
+/// Parse a TOML payroll made of `[[payout]]` tables
+pub fn parse_toml(text: &str) -> Result<Vec<PayrollRow>, PayrollError> {
+    toml::from_str::<PayrollFile>(text)
+        .map(|file| file.payout)
+        .map_err(|error| PayrollError::Parse(error.message().to_string()))
+}
+
+/// Parse a `0x`-prefixed or bare hex address
+pub fn parse_address(value: &str) -> Option<H160> {
+    let bytes = hex::decode(value.trim_start_matches("0x")).ok()?;
+    (bytes.len() == 20).then(|| H160::from_slice(&bytes))
+}
+
+/// Turn payroll rows into payout requests, validating each one with the rules of `add_payouts`.
+/// `token_rules` holds the precision rules of every token the payroll pays in.
+pub fn to_requests(
+    rows: &[PayrollRow],
+    token_rules: &BTreeMap<H160, AssetMetadata>,
+) -> Result<Vec<PayoutRequest>, PayrollError> {
+    rows.iter()
+        .enumerate()
+        .map(|(index, row)| to_request(index + 1, row, token_rules))
+        .collect()
+}
+
+/// Helper function to validate a single row
+fn to_request(
+    row: usize,
+    payout: &PayrollRow,
+    token_rules: &BTreeMap<H160, AssetMetadata>,
+) -> Result<PayoutRequest, PayrollError> {
+    let to = parse_address(&payout.recipient).ok_or(PayrollError::InvalidField {
+        row,
+        field: "recipient",
+    })?;
+    let amount = match &payout.amount {
+        Amount::Integer(amount) => U256::from(*amount),
+        Amount::Text(amount) => {
+            U256::from_dec_str(amount).map_err(|_| PayrollError::InvalidField {
+                row,
+                field: "amount",
+            })?
+        }
+    };
+    let (asset, metadata) = match payout.asset.as_deref() {
+        None | Some("native") => (Asset::Native, NATIVE_ASSET_METADATA),
+        Some(token) => {
+            let token = parse_address(token).ok_or(PayrollError::InvalidField {
+                row,
+                field: "asset",
+            })?;
+            let metadata = token_rules
+                .get(&token)
+                .cloned()
+                .ok_or(PayrollError::UnknownAsset { row, token })?;
+            (Asset::Token(token), metadata)
+        }
+    };
+    let required = |value: Option<u32>, field: &'static str| {
+        value.ok_or(PayrollError::MissingField { row, field })
+    };
+
+    let (request, installments) = match payout.kind.as_str() {
+        "one_time" => (
+            PayoutRequest::OneTime(OneTimeData {
+                to,
+                asset,
+                amount,
+                scheduled_block: payout.scheduled_block,
+                quote: None,
+                time_schedule: None,
+            }),
+            1,
+        ),
+        "recurring" => (
+            PayoutRequest::Recurring(RecurringData {
+                to,
+                asset,
+                amount_per_payment: amount,
+                start_block: payout.start_block,
+                interval_blocks: required(payout.interval_blocks, "interval_blocks")?,
+                total_payments: required(payout.total_payments, "total_payments")?,
+                quote: None,
+                time_schedule: None,
+            }),
+            1,
+        ),
+        "vested" => {
+            let data = VestedData {
+                to,
+                asset,
+                total_amount: amount,
+                cliff_block: payout.cliff_block,
+                vesting_duration_blocks: required(
+                    payout.vesting_duration_blocks,
+                    "vesting_duration_blocks",
+                )?,
+                vesting_interval_blocks: required(
+                    payout.vesting_interval_blocks,
+                    "vesting_interval_blocks",
+                )?,
+                time_schedule: None,
+            };
+            let periods = Treasury::vesting_periods(&data);
+            if periods == 0 {
+                return Err(PayrollError::Rejected {
+                    row,
+                    error: Error::InvalidFrequency,
+                });
+            }
+            (PayoutRequest::Vested(data), periods)
+        }
+        kind => {
+            return Err(PayrollError::UnknownType {
+                row,
+                kind: kind.to_string(),
+            })
+        }
+    };
+
+    Treasury::check_amount_precision(amount, &metadata, installments)
+        .map_err(|error| PayrollError::Rejected { row, error })?;
+    Ok(request)
+}
+
+/// Call data for `add_payouts`: the message selector followed by the SCALE-encoded requests
+pub fn add_payouts_call_data(requests: &[PayoutRequest]) -> Vec<u8> {
+    let mut call_data = ink::selector_bytes!("add_payouts").to_vec();
+    requests.to_vec().encode_to(&mut call_data);
+    call_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_scale_codec::Decode;
+
+    const TOKEN: &str = "0x0101010101010101010101010101010101010101";
+
+    fn stablecoin_rules() -> BTreeMap<H160, AssetMetadata> {
+        BTreeMap::from([(
+            parse_address(TOKEN).unwrap(),
+            AssetMetadata {
+                decimals: 6,
+                min_amount: U256::from(10_000),
+                precision_factor: U256::from(1),
+            },
+        )])
+    }
+
+    #[test]
+    fn csv_and_toml_payrolls_match() {
+        let csv = format!(
+            "# monthly payroll\n\
+             type,recipient,amount,asset,scheduled_block,start_block,interval_blocks,total_payments,vesting_duration_blocks,vesting_interval_blocks\n\
+             one_time,0x0202020202020202020202020202020202020202,5000000,,100,,,,,\n\
+             recurring,0x0303030303030303030303030303030303030303,2500000,{TOKEN},,10,50,12,,\n\
+             vested,0x0404040404040404040404040404040404040404,1000000000000000000000,native,,,,,400,100\n"
+        );
+        let toml = format!(
+            r#"
+            [[payout]]
+            type = "one_time"
+            recipient = "0x0202020202020202020202020202020202020202"
+            amount = 5000000
+            scheduled_block = 100
+
+            [[payout]]
+            type = "recurring"
+            recipient = "0x0303030303030303030303030303030303030303"
+            amount = "2500000"
+            asset = "{TOKEN}"
+            start_block = 10
+            interval_blocks = 50
+            total_payments = 12
+
+            [[payout]]
+            type = "vested"
+            recipient = "0x0404040404040404040404040404040404040404"
+            amount = "1000000000000000000000"
+            asset = "native"
+            vesting_duration_blocks = 400
+            vesting_interval_blocks = 100
+            "#
+        );
+
+        let from_csv = to_requests(&parse_csv(&csv).unwrap(), &stablecoin_rules()).unwrap();
+        let from_toml = to_requests(&parse_toml(&toml).unwrap(), &stablecoin_rules()).unwrap();
+        assert_eq!(from_csv, from_toml);
+        assert_eq!(from_csv.len(), 3);
+        match &from_csv[1] {
+            PayoutRequest::Recurring(data) => {
+                assert_eq!(data.asset, Asset::Token(parse_address(TOKEN).unwrap()));
+                assert_eq!(data.total_payments, 12);
+            }
+            _ => panic!("Expected Recurring request"),
+        }
+
+        // Call data is the selector followed by the encoded requests
+        let call_data = add_payouts_call_data(&from_csv);
+        assert_eq!(call_data[..4], ink::selector_bytes!("add_payouts"));
+        assert_eq!(
+            Vec::<PayoutRequest>::decode(&mut &call_data[4..]).unwrap(),
+            from_csv
+        );
+    }
+
+    #[test]
+    fn rows_are_validated_like_add_payouts() {
+        let header = "type,recipient,amount,asset,interval_blocks,total_payments,vesting_duration_blocks,vesting_interval_blocks\n";
+        let reject = |row: &str| {
+            let rows = parse_csv(&format!("{header}{row}"))
+                .and_then(|rows| to_requests(&rows, &stablecoin_rules()));
+            rows.unwrap_err()
+        };
+
+        // Native amounts must be multiples of 1e6
+        assert_eq!(
+            reject("one_time,0x0202020202020202020202020202020202020202,1500,,,,,"),
+            PayrollError::Rejected {
+                row: 1,
+                error: Error::PrecisionLoss
+            }
+        );
+        // Vesting interval longer than the duration leaves no period
+        assert_eq!(
+            reject("vested,0x0202020202020202020202020202020202020202,4000000,,,,100,400"),
+            PayrollError::Rejected {
+                row: 1,
+                error: Error::InvalidFrequency
+            }
+        );
+        // No vesting period may be dust
+        assert_eq!(
+            reject(&format!(
+                "vested,0x0202020202020202020202020202020202020202,30000,{TOKEN},,,400,100"
+            )),
+            PayrollError::Rejected {
+                row: 1,
+                error: Error::PrecisionLoss
+            }
+        );
+        assert_eq!(
+            reject("recurring,0x0202020202020202020202020202020202020202,1000000,,50,,,"),
+            PayrollError::MissingField {
+                row: 1,
+                field: "total_payments"
+            }
+        );
+        assert_eq!(
+            reject("one_time,0x0202020202020202020202020202020202020202,1000000,0x0909090909090909090909090909090909090909,,,,"),
+            PayrollError::UnknownAsset {
+                row: 1,
+                token: H160::from([9u8; 20])
+            }
+        );
+        assert_eq!(
+            reject("bonus,0x0202,1000000,,,,,"),
+            PayrollError::InvalidField {
+                row: 1,
+                field: "recipient"
+            }
+        );
+        assert_eq!(
+            parse_csv("type,recipient,salary\n"),
+            Err(PayrollError::Parse("unknown column `salary`".into()))
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! Operator CLI exporting the payouts of a deployed contract_treasury_v6 instance from its raw
+//! storage into `import_v6_payouts` call data for the typed treasury.
+
+mod v6_storage;
+
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: treasury-v6-export keys [<root.hex>]
+       treasury-v6-export import <root.hex> [<past_payout.hex>...] [--out <file>]
+
+Reads the storage of a contract_treasury_v6 instance fetched with `childstate_getStorage` from
+the contract's child trie, and prints the SCALE-encoded `import_v6_payouts` call data.
+
+Commands:
+  keys      Print the child trie key of the root storage value. Given the root value, also
+            print the key of every past payout it lists.
+  import    Decode the root value and the past payout values and print the call data
+
+Options:
+  --out <file>    Write the raw call data to <file> instead of printing it";
+
+/// Helper function to read a file holding a `0x`-prefixed or bare hex storage value
+fn read_value(path: &str) -> Result<Vec<u8>, String> {
+    let text =
+        std::fs::read_to_string(path).map_err(|error| format!("cannot read {path}: {error}"))?;
+    hex::decode(text.trim().trim_start_matches("0x"))
+        .map_err(|error| format!("{path} is not hex: {error}"))
+}
+
+fn keys(root: Option<&String>) -> Result<(), String> {
+    println!(
+        "root 0x{}",
+        hex::encode(v6_storage::child_trie_key(&v6_storage::ROOT_KEY))
+    );
+    if let Some(path) = root {
+        let root = v6_storage::decode_root(&read_value(path)?).map_err(|e| e.to_string())?;
+        for id in root.past_payout_ids {
+            let key = v6_storage::child_trie_key(&v6_storage::past_payout_key(id));
+            println!("past_payout {id} 0x{}", hex::encode(key));
+        }
+    }
+    Ok(())
+}
+
+fn import(paths: &[String], out: Option<String>) -> Result<(), String> {
+    let (root, past) = paths.split_first().ok_or(USAGE)?;
+    let root = v6_storage::decode_root(&read_value(root)?).map_err(|e| e.to_string())?;
+    let past_payouts = past
+        .iter()
+        .map(|path| v6_storage::decode_past_payout(&read_value(path)?).map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let payouts = v6_storage::to_export(&root, &past_payouts).map_err(|e| e.to_string())?;
+    let call_data = v6_storage::import_call_data(&payouts);
+
+    match out {
+        Some(path) => std::fs::write(&path, &call_data)
+            .map_err(|error| format!("cannot write {path}: {error}"))?,
+        None => println!("0x{}", hex::encode(&call_data)),
+    }
+    eprintln!("{} payouts exported", payouts.len());
+    Ok(())
+}
+
+fn run(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let command = args.next().ok_or(USAGE)?;
+    let mut paths = Vec::new();
+    let mut out = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out = Some(args.next().ok_or("--out needs a value")?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if !arg.starts_with("--") => paths.push(arg),
+            _ => return Err(format!("unexpected argument `{arg}`\n\n{USAGE}")),
+        }
+    }
+
+    match command.as_str() {
+        "keys" if paths.len() <= 1 && out.is_none() => keys(paths.first()),
+        "import" => import(&paths, out),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Export of payouts from the raw storage of a deployed contract_treasury_v6 instance.
+//!
+//! The live instance can't gain the `export_payouts` message, it has no way to change its
+//! code, so its storage is read off-chain instead. ink keeps the root `Treasury` struct under
+//! the key `0x00000000` and each archived payout under the `past_payouts` mapping key followed
+//! by the SCALE-encoded payout ID. pallet-revive stores every value in the contract's child trie
+//! (`:child_storage:default:` followed by the contract's `trie_id`) at `blake2_128(key) ++ key`,
+//! which `childstate_getStorage` returns as is.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use ink::env::hash::{Blake2x128, CryptoHash};
+use ink::primitives::KeyComposer;
+use ink::{H160, U256};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use treasury::treasury::{PayoutType, V6Payout, V6PayoutStatus};
+
+/// ink storage key of the root `Treasury` struct
+pub const ROOT_KEY: [u8; 4] = [0; 4];
+
+/// Status of a payout in the deployed v6 contract
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub enum StoredStatus {
+    Pending,
+    Active,
+    Completed,
+    Cancelled,
+}
+
+/// `Payout` as laid out by the deployed v6 contract
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct StoredPayout {
+    pub id: u32,
+    pub to: H160,
+    pub amount: U256,
+    pub block_number: u32,
+    pub approvals: Vec<H160>,
+    pub payout_type: PayoutType,
+    pub status: StoredStatus,
+    pub interval_blocks: u32,
+    pub total_payouts: u32,
+    pub completed_payouts: u32,
+    pub cliff_blocks: u32,
+    pub cancellation_approvals: Vec<H160>,
+}
+
+/// Approval threshold of the deployed v6 contract
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct Threshold {
+    pub min_amount: U256,
+    pub max_amount: U256,
+    pub required_approvals: u32,
+}
+
+/// Root storage of the deployed v6 contract. `past_payouts` is a mapping, its entries
+/// live under their own keys and take no space here.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct StoredTreasury {
+    pub owner: H160,
+    pub treasurers: BTreeSet<H160>,
+    pub pending_payouts: Vec<StoredPayout>,
+    pub past_payout_ids: Vec<u32>,
+    pub thresholds: Vec<Threshold>,
+    pub processing: bool,
+    pub next_payout_id: u32,
+}
+
+impl StoredTreasury {
+    /// Approvals a payout of `amount` needs, as computed by v6's `get_required_approvals`
+    pub fn required_approvals(&self, amount: U256) -> u32 {
+        self.thresholds
+            .iter()
+            .find(|t| amount >= t.min_amount && amount <= t.max_amount)
+            .map(|t| t.required_approvals)
+            .unwrap_or(1)
+            .min(u32::try_from(self.treasurers.len()).unwrap_or(u32::MAX))
+    }
+}
+
+/// Errors while reading the storage of a v6 instance
+#[derive(Debug, PartialEq)]
+pub enum ExportError {
+    /// A value doesn't decode as the deployed v6 layout
+    Decode(String),
+    /// `past_payout_ids` lists a payout whose value wasn't supplied
+    MissingPastPayout(u32),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(what) => write!(f, "{what} doesn't match the deployed v6 layout"),
+            Self::MissingPastPayout(id) => write!(f, "no storage value for past payout {id}"),
+        }
+    }
+}
+
+/// ink storage key of the `past_payouts` entry of a payout
+pub fn past_payout_key(id: u32) -> Vec<u8> {
+    let mapping_key = KeyComposer::compute_key("Treasury", "", "past_payouts")
+        .expect("struct and field names are not empty");
+    (mapping_key, id).encode()
+}
+
+/// Key of a storage value in the contract's child trie
+pub fn child_trie_key(key: &[u8]) -> Vec<u8> {
+    let mut hash = [0u8; 16];
+    Blake2x128::hash(key, &mut hash);
+    [&hash[..], key].concat()
+}
+
+/// Decode the root storage value. The whole value has to be consumed, so the storage of
+/// later v6 builds with a longer `Payout` is rejected rather than misread.
+pub fn decode_root(value: &[u8]) -> Result<StoredTreasury, ExportError> {
+    StoredTreasury::decode_all(&mut &value[..])
+        .map_err(|_| ExportError::Decode("root storage value".into()))
+}
+
+/// Decode the storage value of an archived payout
+pub fn decode_past_payout(value: &[u8]) -> Result<StoredPayout, ExportError> {
+    StoredPayout::decode_all(&mut &value[..])
+        .map_err(|_| ExportError::Decode("past payout value".into()))
+}
+
+/// Map the stored payouts onto `import_v6_payouts` records, pending payouts first followed by
+/// past payouts in the order they were archived, like v6's `export_payouts`
+pub fn to_export(
+    root: &StoredTreasury,
+    past_payouts: &[StoredPayout],
+) -> Result<Vec<V6Payout>, ExportError> {
+    let archived = root
+        .past_payout_ids
+        .iter()
+        .map(|id| {
+            past_payouts
+                .iter()
+                .find(|payout| payout.id == *id)
+                .ok_or(ExportError::MissingPastPayout(*id))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(root
+        .pending_payouts
+        .iter()
+        .chain(archived)
+        .map(|payout| V6Payout {
+            id: payout.id,
+            to: payout.to,
+            amount: payout.amount,
+            payout_type: payout.payout_type.clone(),
+            status: match payout.status {
+                StoredStatus::Pending => V6PayoutStatus::Pending,
+                StoredStatus::Active => V6PayoutStatus::Active,
+                StoredStatus::Completed => V6PayoutStatus::Completed,
+                StoredStatus::Cancelled => V6PayoutStatus::Cancelled,
+            },
+            block_number: payout.block_number,
+            interval_blocks: payout.interval_blocks,
+            total_payouts: payout.total_payouts,
+            completed_payouts: payout.completed_payouts,
+            cliff_blocks: payout.cliff_blocks,
+            approvals: u32::try_from(payout.approvals.len()).unwrap_or(u32::MAX),
+            required_approvals: root.required_approvals(payout.amount),
+        })
+        .collect())
+}
+
+/// Call data for `import_v6_payouts`: the message selector followed by the SCALE-encoded payouts
+pub fn import_call_data(payouts: &[V6Payout]) -> Vec<u8> {
+    let mut call_data = ink::selector_bytes!("import_v6_payouts").to_vec();
+    payouts.to_vec().encode_to(&mut call_data);
+    call_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored_payout(id: u32, payout_type: PayoutType, status: StoredStatus) -> StoredPayout {
+        StoredPayout {
+            id,
+            to: H160::from([2u8; 20]),
+            amount: U256::from(2_000_000),
+            block_number: 100,
+            approvals: vec![H160::from([1u8; 20])],
+            payout_type,
+            status,
+            interval_blocks: 10,
+            total_payouts: 3,
+            completed_payouts: 0,
+            cliff_blocks: 0,
+            cancellation_approvals: Vec::new(),
+        }
+    }
+
+    fn stored_treasury() -> StoredTreasury {
+        let mut medium = stored_payout(2, PayoutType::OneTime, StoredStatus::Pending);
+        medium.amount = U256::from(1_000_000_000_000_u128);
+        let mut active = stored_payout(3, PayoutType::Recurring, StoredStatus::Active);
+        active.completed_payouts = 1;
+        StoredTreasury {
+            owner: H160::from([9u8; 20]),
+            treasurers: BTreeSet::from([H160::from([1u8; 20]), H160::from([3u8; 20])]),
+            pending_payouts: vec![
+                stored_payout(1, PayoutType::OneTime, StoredStatus::Pending),
+                medium,
+                active,
+            ],
+            past_payout_ids: vec![0],
+            thresholds: vec![
+                Threshold {
+                    min_amount: U256::zero(),
+                    max_amount: U256::from(999_999_999_999_u128),
+                    required_approvals: 1,
+                },
+                Threshold {
+                    min_amount: U256::from(1_000_000_000_000_u128),
+                    max_amount: U256::MAX,
+                    required_approvals: 2,
+                },
+            ],
+            processing: false,
+            next_payout_id: 4,
+        }
+    }
+
+    #[test]
+    fn storage_exports_to_import_call_data() {
+        let root = stored_treasury().encode();
+        let past = stored_payout(0, PayoutType::OneTime, StoredStatus::Completed).encode();
+
+        let root = decode_root(&root).unwrap();
+        let past_payouts = vec![decode_past_payout(&past).unwrap()];
+        let exported = to_export(&root, &past_payouts).unwrap();
+
+        assert_eq!(
+            exported.iter().map(|payout| payout.id).collect::<Vec<_>>(),
+            vec![1, 2, 3, 0]
+        );
+        // Approved but not processed yet, and short of its threshold
+        assert_eq!(exported[0].status, V6PayoutStatus::Pending);
+        assert_eq!(
+            (exported[0].approvals, exported[0].required_approvals),
+            (1, 1)
+        );
+        assert_eq!(
+            (exported[1].approvals, exported[1].required_approvals),
+            (1, 2)
+        );
+        assert_eq!(exported[2].status, V6PayoutStatus::Active);
+        assert_eq!(exported[2].completed_payouts, 1);
+        assert_eq!(exported[3].status, V6PayoutStatus::Completed);
+
+        let call_data = import_call_data(&exported);
+        assert_eq!(call_data[..4], ink::selector_bytes!("import_v6_payouts"));
+        assert_eq!(
+            Vec::<V6Payout>::decode(&mut &call_data[4..]).unwrap(),
+            exported
+        );
+
+        assert_eq!(
+            to_export(&root, &[]),
+            Err(ExportError::MissingPastPayout(0))
+        );
+    }
+
+    #[test]
+    fn later_layouts_are_rejected() {
+        // Later v6 builds store more fields per payout, the extra bytes must not be ignored
+        let mut value = stored_payout(0, PayoutType::OneTime, StoredStatus::Completed).encode();
+        value.extend(0u32.encode());
+        assert_eq!(
+            decode_past_payout(&value),
+            Err(ExportError::Decode("past payout value".into()))
+        );
+        assert!(decode_root(&[0u8; 3]).is_err());
+    }
+
+    #[test]
+    fn storage_keys_match_ink() {
+        // Root key of `past_payouts` in the v6 metadata, frontend/.papi/contracts/treasury_v6.json
+        assert_eq!(past_payout_key(7), [0x20, 0x42, 0xd9, 0xba, 7, 0, 0, 0]);
+
+        let trie_key = child_trie_key(&ROOT_KEY);
+        assert_eq!(trie_key.len(), 20);
+        assert_eq!(trie_key[16..], ROOT_KEY);
+    }
+}
@@ -0,0 +1,1100 @@
+//! Event indexer for `contract_treasury`.
+//!
+//! Decodes the SCALE-encoded events of a treasury contract with the contract's own types and
+//! folds them into an in-memory ledger of payouts, payments, distributions, escrows, recipients
+//! and funders. Configuration, recovery and address change request events are not tracked.
+
+use std::collections::BTreeMap;
+
+use ink::{H160, U256};
+use parity_scale_codec::{Decode, DecodeAll};
+use treasury::treasury::{
+    Asset, AssetTotals, DeferReason, Distribution, DistributionClaimed, DistributionClosed,
+    DistributionCreated, Escrow, EscrowCreated, EscrowDisputed, EscrowRefunded, EscrowReleased,
+    EscrowStatus, EscrowVoted, FundsAdded, InstallmentScheduled, KeeperRewarded, Payout,
+    PayoutAdded, PayoutAmended, PayoutCancelled, PayoutCompleted, PayoutDeferred, PayoutPaid,
+    PayoutPaused, PayoutResumed, PayoutShare, PayoutSharesSet, PayoutType, PayoutsProcessed,
+    RecipientAddressChanged, TreasuryCreated, V6PayoutsImported,
+};
+
+/// An event as read from the chain, together with the block it was emitted in
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawEvent {
+    pub block: u32,
+    pub topics: Vec<[u8; 32]>, // The first topic is the event signature
+    pub data: Vec<u8>,
+}
+
+/// Treasury events the ledger folds
+pub enum TreasuryEvent {
+    TreasuryCreated(TreasuryCreated),
+    PayoutAdded(PayoutAdded),
+    PayoutAmended(PayoutAmended),
+    PayoutPaused(PayoutPaused),
+    PayoutResumed(PayoutResumed),
+    PayoutSharesSet(PayoutSharesSet),
+    RecipientAddressChanged(RecipientAddressChanged),
+    InstallmentScheduled(InstallmentScheduled),
+    PayoutPaid(PayoutPaid),
+    PayoutCompleted(PayoutCompleted),
+    PayoutCancelled(PayoutCancelled),
+    PayoutDeferred(PayoutDeferred),
+    PayoutsProcessed(PayoutsProcessed),
+    FundsAdded(FundsAdded),
+    KeeperRewarded(KeeperRewarded),
+    V6PayoutsImported(V6PayoutsImported),
+    DistributionCreated(DistributionCreated),
+    DistributionClaimed(DistributionClaimed),
+    DistributionClosed(DistributionClosed),
+    EscrowCreated(EscrowCreated),
+    EscrowDisputed(EscrowDisputed),
+    EscrowVoted(EscrowVoted),
+    EscrowReleased(EscrowReleased),
+    EscrowRefunded(EscrowRefunded),
+}
+
+/// Errors while indexing treasury events
+#[derive(Debug, Clone, PartialEq)]
+pub enum IndexerError {
+    /// A tracked event whose data doesn't decode as that event
+    InvalidEventData { block: u32, event: &'static str },
+    /// An event refers to a payout the ledger never saw being added
+    UnknownPayout(u32),
+    /// An event refers to a distribution the ledger never saw being created
+    UnknownDistribution(u32),
+    /// An event refers to an escrow the ledger never saw being created
+    UnknownEscrow(u32),
+}
+
+/// Helper function to check an event signature against an event type
+fn is_event<E: ink::env::Event>(signature: &[u8; 32]) -> bool {
+    E::SIGNATURE_TOPIC.as_ref() == Some(signature)
+}
+
+/// Helper function to decode the whole event data as an event type
+fn decode_event<E: Decode>(raw: &RawEvent, event: &'static str) -> Result<E, IndexerError> {
+    E::decode_all(&mut &raw.data[..]).map_err(|_| IndexerError::InvalidEventData {
+        block: raw.block,
+        event,
+    })
+}
+
+impl TreasuryEvent {
+    /// Decode a raw event, `None` for events the ledger doesn't track
+    pub fn decode(raw: &RawEvent) -> Result<Option<Self>, IndexerError> {
+        let Some(signature) = raw.topics.first() else {
+            return Ok(None);
+        };
+
+        let event = if is_event::<TreasuryCreated>(signature) {
+            Self::TreasuryCreated(decode_event(raw, "TreasuryCreated")?)
+        } else if is_event::<PayoutAdded>(signature) {
+            Self::PayoutAdded(decode_event(raw, "PayoutAdded")?)
+        } else if is_event::<PayoutAmended>(signature) {
+            Self::PayoutAmended(decode_event(raw, "PayoutAmended")?)
+        } else if is_event::<PayoutPaused>(signature) {
+            Self::PayoutPaused(decode_event(raw, "PayoutPaused")?)
+        } else if is_event::<PayoutResumed>(signature) {
+            Self::PayoutResumed(decode_event(raw, "PayoutResumed")?)
+        } else if is_event::<PayoutSharesSet>(signature) {
+            Self::PayoutSharesSet(decode_event(raw, "PayoutSharesSet")?)
+        } else if is_event::<RecipientAddressChanged>(signature) {
+            Self::RecipientAddressChanged(decode_event(raw, "RecipientAddressChanged")?)
+        } else if is_event::<InstallmentScheduled>(signature) {
+            Self::InstallmentScheduled(decode_event(raw, "InstallmentScheduled")?)
+        } else if is_event::<PayoutPaid>(signature) {
+            Self::PayoutPaid(decode_event(raw, "PayoutPaid")?)
+        } else if is_event::<PayoutCompleted>(signature) {
+            Self::PayoutCompleted(decode_event(raw, "PayoutCompleted")?)
+        } else if is_event::<PayoutCancelled>(signature) {
+            Self::PayoutCancelled(decode_event(raw, "PayoutCancelled")?)
+        } else if is_event::<PayoutDeferred>(signature) {
+            Self::PayoutDeferred(decode_event(raw, "PayoutDeferred")?)
+        } else if is_event::<PayoutsProcessed>(signature) {
+            Self::PayoutsProcessed(decode_event(raw, "PayoutsProcessed")?)
+        } else if is_event::<FundsAdded>(signature) {
+            Self::FundsAdded(decode_event(raw, "FundsAdded")?)
+        } else if is_event::<KeeperRewarded>(signature) {
+            Self::KeeperRewarded(decode_event(raw, "KeeperRewarded")?)
+        } else if is_event::<V6PayoutsImported>(signature) {
+            Self::V6PayoutsImported(decode_event(raw, "V6PayoutsImported")?)
+        } else if is_event::<DistributionCreated>(signature) {
+            Self::DistributionCreated(decode_event(raw, "DistributionCreated")?)
+        } else if is_event::<DistributionClaimed>(signature) {
+            Self::DistributionClaimed(decode_event(raw, "DistributionClaimed")?)
+        } else if is_event::<DistributionClosed>(signature) {
+            Self::DistributionClosed(decode_event(raw, "DistributionClosed")?)
+        } else if is_event::<EscrowCreated>(signature) {
+            Self::EscrowCreated(decode_event(raw, "EscrowCreated")?)
+        } else if is_event::<EscrowDisputed>(signature) {
+            Self::EscrowDisputed(decode_event(raw, "EscrowDisputed")?)
+        } else if is_event::<EscrowVoted>(signature) {
+            Self::EscrowVoted(decode_event(raw, "EscrowVoted")?)
+        } else if is_event::<EscrowReleased>(signature) {
+            Self::EscrowReleased(decode_event(raw, "EscrowReleased")?)
+        } else if is_event::<EscrowRefunded>(signature) {
+            Self::EscrowRefunded(decode_event(raw, "EscrowRefunded")?)
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(event))
+    }
+}
+
+/// State of a payout as reconstructed from its events
+#[derive(Debug, Clone, PartialEq)]
+pub enum PayoutState {
+    Pending,
+    Completed(u32), // block the payout was archived in
+    Cancelled(u32), // block the payout was cancelled in
+    Paused(u32),    // block the payout was paused in, it stays in the pending set
+}
+
+/// A payout known to the ledger
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayoutRecord {
+    pub id: u32,
+    pub to: H160,
+    pub payout_type: PayoutType,
+    pub payout: Payout,
+    pub state: PayoutState,
+    pub added_block: u32,
+    pub previous_payout_id: Option<u32>, // Set for recurring and vested follow-ups
+    pub revisions: Vec<Payout>,          // Versions replaced by amendments, oldest first
+    pub shares: Vec<PayoutShare>,        // Recipients paid instead of `to` when split
+    pub last_deferral: Option<DeferReason>,
+}
+
+/// A single transfer made by the treasury for a payout
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payment {
+    pub payout_id: u32,
+    pub to: H160,
+    pub asset: Asset, // Asset of the payout, the event doesn't repeat it
+    pub amount: U256,
+    pub installment: u32,
+    pub block: u32,
+}
+
+/// A distribution known to the ledger
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistributionRecord {
+    pub id: u32,
+    pub distribution: Distribution, // `claimed_amount` and `closed` follow the claim and close events
+    pub created_block: u32,
+    pub claims: Vec<DistributionClaim>,
+    pub closed_block: Option<u32>,
+}
+
+/// A claimed leaf of a distribution
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistributionClaim {
+    pub to: H160,
+    pub index: u32,
+    pub amount: U256,
+    pub block: u32,
+}
+
+/// An escrow known to the ledger
+#[derive(Debug, Clone, PartialEq)]
+pub struct EscrowRecord {
+    pub id: u32,
+    pub escrow: Escrow, // Status and votes follow the escrow events
+    pub created_block: u32,
+}
+
+/// One `process_payouts` run
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessedBatch {
+    pub block: u32,
+    pub payout_ids: Vec<u32>,
+    pub asset_totals: AssetTotals,
+}
+
+/// Ledger of a single treasury contract, built by applying its events in order
+#[derive(Debug, Clone, Default)]
+pub struct Ledger {
+    owner: Option<H160>,
+    payouts: BTreeMap<u32, PayoutRecord>,
+    pending_ids: Vec<u32>, // Same order as the contract's `get_pending_payout_ids`
+    payments: Vec<Payment>,
+    batches: Vec<ProcessedBatch>,
+    imported_ids: Vec<u32>,
+    distributions: BTreeMap<u32, DistributionRecord>,
+    escrows: BTreeMap<u32, EscrowRecord>,
+    funders: BTreeMap<H160, U256>,
+    keeper_rewards: BTreeMap<H160, U256>,
+    last_block: u32,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a batch of events in emission order
+    pub fn apply_all<'a>(
+        &mut self,
+        events: impl IntoIterator<Item = &'a RawEvent>,
+    ) -> Result<(), IndexerError> {
+        for raw in events {
+            self.apply(raw)?;
+        }
+        Ok(())
+    }
+
+    /// Apply a single event, ignoring events the ledger doesn't track
+    pub fn apply(&mut self, raw: &RawEvent) -> Result<(), IndexerError> {
+        let Some(event) = TreasuryEvent::decode(raw)? else {
+            self.last_block = self.last_block.max(raw.block);
+            return Ok(());
+        };
+
+        match event {
+            TreasuryEvent::TreasuryCreated(created) => self.owner = Some(created.owner),
+            TreasuryEvent::PayoutAdded(added) => {
+                self.insert_pending(PayoutRecord {
+                    id: added.payout_id,
+                    to: added.to,
+                    payout_type: added.payout_type,
+                    payout: added.payout_data,
+                    state: PayoutState::Pending,
+                    added_block: raw.block,
+                    previous_payout_id: None,
+                    revisions: Vec::new(),
+                    shares: Vec::new(),
+                    last_deferral: None,
+                });
+            }
+            TreasuryEvent::PayoutAmended(amended) => {
+                let record = self
+                    .payouts
+                    .get_mut(&amended.payout_id)
+                    .ok_or(IndexerError::UnknownPayout(amended.payout_id))?;
+                record.to = match &amended.amended {
+                    Payout::OneTime(stored) => stored.data.to,
+                    Payout::Recurring(stored) => stored.data.to,
+                    Payout::Vested(stored) => stored.data.to,
+                };
+                record
+                    .revisions
+                    .push(core::mem::replace(&mut record.payout, amended.amended));
+            }
+            TreasuryEvent::PayoutPaused(paused) => {
+                let record = self
+                    .payouts
+                    .get_mut(&paused.payout_id)
+                    .ok_or(IndexerError::UnknownPayout(paused.payout_id))?;
+                record.state = PayoutState::Paused(raw.block);
+            }
+            TreasuryEvent::PayoutResumed(resumed) => {
+                let record = self
+                    .payouts
+                    .get_mut(&resumed.payout_id)
+                    .ok_or(IndexerError::UnknownPayout(resumed.payout_id))?;
+                record.state = PayoutState::Pending;
+                record.payout = resumed.payout_data;
+            }
+            TreasuryEvent::PayoutSharesSet(set) => {
+                let record = self
+                    .payouts
+                    .get_mut(&set.payout_id)
+                    .ok_or(IndexerError::UnknownPayout(set.payout_id))?;
+                record.shares = set.shares;
+            }
+            TreasuryEvent::RecipientAddressChanged(changed) => {
+                for payout_id in changed.payout_ids {
+                    let record = self
+                        .payouts
+                        .get_mut(&payout_id)
+                        .ok_or(IndexerError::UnknownPayout(payout_id))?;
+                    if record.to == changed.from {
+                        record.to = changed.to;
+                        match &mut record.payout {
+                            Payout::OneTime(stored) => stored.data.to = changed.to,
+                            Payout::Recurring(stored) => stored.data.to = changed.to,
+                            Payout::Vested(stored) => stored.data.to = changed.to,
+                        }
+                    }
+                    // Same merge as the contract: the new address keeps a single share
+                    if let Some(position) = record
+                        .shares
+                        .iter()
+                        .position(|share| share.to == changed.from)
+                    {
+                        let moved = record.shares.remove(position);
+                        match record
+                            .shares
+                            .iter_mut()
+                            .find(|share| share.to == changed.to)
+                        {
+                            Some(share) => share.basis_points += moved.basis_points,
+                            None => record.shares.insert(
+                                position,
+                                PayoutShare {
+                                    to: changed.to,
+                                    ..moved
+                                },
+                            ),
+                        }
+                    }
+                }
+                for escrow_id in changed.escrow_ids {
+                    self.escrow_mut(escrow_id)?.escrow.terms.to = changed.to;
+                }
+            }
+            TreasuryEvent::InstallmentScheduled(scheduled) => {
+                let previous = self.record(scheduled.previous_payout_id)?;
+                let payout_type = previous.payout_type.clone();
+                let shares = previous.shares.clone();
+                self.insert_pending(PayoutRecord {
+                    id: scheduled.payout_id,
+                    to: scheduled.to,
+                    payout_type,
+                    payout: scheduled.payout_data,
+                    state: PayoutState::Pending,
+                    added_block: raw.block,
+                    previous_payout_id: Some(scheduled.previous_payout_id),
+                    revisions: Vec::new(),
+                    shares,
+                    last_deferral: None,
+                });
+            }
+            TreasuryEvent::PayoutPaid(paid) => {
+                let asset = match &self.record(paid.payout_id)?.payout {
+                    Payout::OneTime(stored) => stored.data.asset,
+                    Payout::Recurring(stored) => stored.data.asset,
+                    Payout::Vested(stored) => stored.data.asset,
+                };
+                self.payments.push(Payment {
+                    payout_id: paid.payout_id,
+                    to: paid.to,
+                    asset,
+                    amount: paid.amount,
+                    installment: paid.installment,
+                    block: raw.block,
+                });
+            }
+            TreasuryEvent::PayoutCompleted(completed) => {
+                self.close(completed.payout_id, PayoutState::Completed(raw.block))?;
+            }
+            TreasuryEvent::PayoutCancelled(cancelled) => {
+                self.close(cancelled.payout_id, PayoutState::Cancelled(raw.block))?;
+            }
+            TreasuryEvent::PayoutDeferred(deferred) => {
+                let record = self
+                    .payouts
+                    .get_mut(&deferred.payout_id)
+                    .ok_or(IndexerError::UnknownPayout(deferred.payout_id))?;
+                record.last_deferral = Some(deferred.reason);
+            }
+            TreasuryEvent::PayoutsProcessed(processed) => {
+                self.batches.push(ProcessedBatch {
+                    block: raw.block,
+                    payout_ids: processed.processed_ids,
+                    asset_totals: processed.asset_totals,
+                });
+            }
+            TreasuryEvent::FundsAdded(funds) => {
+                let funded = self.funders.entry(funds.from).or_default();
+                *funded = funded.saturating_add(funds.amount);
+            }
+            TreasuryEvent::KeeperRewarded(rewarded) => {
+                let earned = self.keeper_rewards.entry(rewarded.keeper).or_default();
+                *earned = earned.saturating_add(rewarded.amount);
+            }
+            TreasuryEvent::V6PayoutsImported(imported) => {
+                self.imported_ids.extend(imported.imported_ids);
+            }
+            TreasuryEvent::DistributionCreated(created) => {
+                self.distributions.insert(
+                    created.distribution_id,
+                    DistributionRecord {
+                        id: created.distribution_id,
+                        distribution: created.distribution,
+                        created_block: raw.block,
+                        claims: Vec::new(),
+                        closed_block: None,
+                    },
+                );
+            }
+            TreasuryEvent::DistributionClaimed(claimed) => {
+                let record = self.distribution_mut(claimed.distribution_id)?;
+                record.distribution.claimed_amount = record
+                    .distribution
+                    .claimed_amount
+                    .saturating_add(claimed.amount);
+                record.claims.push(DistributionClaim {
+                    to: claimed.to,
+                    index: claimed.index,
+                    amount: claimed.amount,
+                    block: raw.block,
+                });
+            }
+            TreasuryEvent::DistributionClosed(closed) => {
+                let record = self.distribution_mut(closed.distribution_id)?;
+                record.distribution.closed = true;
+                record.closed_block = Some(raw.block);
+            }
+            TreasuryEvent::EscrowCreated(created) => {
+                self.escrows.insert(
+                    created.escrow_id,
+                    EscrowRecord {
+                        id: created.escrow_id,
+                        escrow: created.escrow,
+                        created_block: raw.block,
+                    },
+                );
+            }
+            TreasuryEvent::EscrowDisputed(disputed) => {
+                self.escrow_mut(disputed.escrow_id)?.escrow.status =
+                    EscrowStatus::Disputed(raw.block);
+            }
+            TreasuryEvent::EscrowVoted(voted) => {
+                // Same as the contract: an arbiter's latest vote replaces its earlier one
+                let escrow = &mut self.escrow_mut(voted.escrow_id)?.escrow;
+                escrow.release_votes.retain(|voter| *voter != voted.arbiter);
+                escrow.refund_votes.retain(|voter| *voter != voted.arbiter);
+                if voted.release {
+                    escrow.release_votes.push(voted.arbiter);
+                } else {
+                    escrow.refund_votes.push(voted.arbiter);
+                }
+            }
+            TreasuryEvent::EscrowReleased(released) => {
+                let escrow = &mut self.escrow_mut(released.escrow_id)?.escrow;
+                escrow.terms.to = released.to;
+                escrow.status = EscrowStatus::Released(raw.block);
+            }
+            TreasuryEvent::EscrowRefunded(refunded) => {
+                self.escrow_mut(refunded.escrow_id)?.escrow.status =
+                    EscrowStatus::Refunded(raw.block);
+            }
+        }
+
+        self.last_block = self.last_block.max(raw.block);
+        Ok(())
+    }
+
+    /// Helper function to track a newly added payout as pending
+    fn insert_pending(&mut self, record: PayoutRecord) {
+        self.pending_ids.push(record.id);
+        self.payouts.insert(record.id, record);
+    }
+
+    /// Helper function to move a payout out of the pending set
+    fn close(&mut self, payout_id: u32, state: PayoutState) -> Result<(), IndexerError> {
+        let record = self
+            .payouts
+            .get_mut(&payout_id)
+            .ok_or(IndexerError::UnknownPayout(payout_id))?;
+        record.state = state;
+        self.pending_ids.retain(|id| *id != payout_id);
+        Ok(())
+    }
+
+    /// Helper function to look up a payout an event refers to
+    fn record(&self, payout_id: u32) -> Result<&PayoutRecord, IndexerError> {
+        self.payouts
+            .get(&payout_id)
+            .ok_or(IndexerError::UnknownPayout(payout_id))
+    }
+
+    /// Helper function to look up a distribution an event refers to
+    fn distribution_mut(
+        &mut self,
+        distribution_id: u32,
+    ) -> Result<&mut DistributionRecord, IndexerError> {
+        self.distributions
+            .get_mut(&distribution_id)
+            .ok_or(IndexerError::UnknownDistribution(distribution_id))
+    }
+
+    /// Helper function to look up an escrow an event refers to
+    fn escrow_mut(&mut self, escrow_id: u32) -> Result<&mut EscrowRecord, IndexerError> {
+        self.escrows
+            .get_mut(&escrow_id)
+            .ok_or(IndexerError::UnknownEscrow(escrow_id))
+    }
+
+    pub fn owner(&self) -> Option<H160> {
+        self.owner
+    }
+
+    /// Highest block of any event applied so far
+    pub fn last_block(&self) -> u32 {
+        self.last_block
+    }
+
+    pub fn payout(&self, payout_id: u32) -> Option<&PayoutRecord> {
+        self.payouts.get(&payout_id)
+    }
+
+    pub fn pending_payout_ids(&self) -> &[u32] {
+        &self.pending_ids
+    }
+
+    /// All payouts to a recipient, including follow-up installments, by ID
+    pub fn payouts_for(&self, recipient: H160) -> Vec<&PayoutRecord> {
+        self.payouts
+            .values()
+            .filter(|record| record.to == recipient)
+            .collect()
+    }
+
+    /// Every recipient of a known payout or of a share of one, by current address
+    pub fn recipients(&self) -> Vec<H160> {
+        let mut recipients: Vec<H160> = self
+            .payouts
+            .values()
+            .flat_map(|record| {
+                core::iter::once(record.to).chain(record.shares.iter().map(|share| share.to))
+            })
+            .collect();
+        recipients.sort();
+        recipients.dedup();
+        recipients
+    }
+
+    /// Payments in the order they were made
+    pub fn payments(&self) -> &[Payment] {
+        &self.payments
+    }
+
+    pub fn payments_to(&self, recipient: H160) -> Vec<&Payment> {
+        self.payments
+            .iter()
+            .filter(|payment| payment.to == recipient)
+            .collect()
+    }
+
+    /// Amount paid to a recipient per asset, through payouts, distribution claims and
+    /// released escrows
+    pub fn total_paid_to(&self, recipient: H160) -> AssetTotals {
+        let payments = self
+            .payments_to(recipient)
+            .into_iter()
+            .map(|payment| (payment.asset, payment.amount));
+        let claims = self.distributions.values().flat_map(|record| {
+            record
+                .claims
+                .iter()
+                .filter(|claim| claim.to == recipient)
+                .map(|claim| (record.distribution.asset, claim.amount))
+        });
+        let escrows = self
+            .escrows
+            .values()
+            .map(|record| &record.escrow)
+            .filter(|escrow| {
+                escrow.terms.to == recipient && matches!(escrow.status, EscrowStatus::Released(_))
+            })
+            .map(|escrow| (escrow.terms.asset, escrow.terms.amount));
+
+        let mut totals = AssetTotals::new();
+        for (asset, amount) in payments.chain(claims).chain(escrows) {
+            match totals.iter_mut().find(|(a, _)| *a == asset) {
+                Some((_, total)) => *total = total.saturating_add(amount),
+                None => totals.push((asset, amount)),
+            }
+        }
+        totals
+    }
+
+    pub fn batches(&self) -> &[ProcessedBatch] {
+        &self.batches
+    }
+
+    /// IDs imported from contract_treasury_v6. Payouts imported as history have no record,
+    /// no event carries their data.
+    pub fn imported_payout_ids(&self) -> &[u32] {
+        &self.imported_ids
+    }
+
+    pub fn distribution(&self, distribution_id: u32) -> Option<&DistributionRecord> {
+        self.distributions.get(&distribution_id)
+    }
+
+    pub fn escrow(&self, escrow_id: u32) -> Option<&EscrowRecord> {
+        self.escrows.get(&escrow_id)
+    }
+
+    /// Funders and the total each has sent through `fund`
+    pub fn funders(&self) -> Vec<(H160, U256)> {
+        self.funders
+            .iter()
+            .map(|(funder, amount)| (*funder, *amount))
+            .collect()
+    }
+
+    pub fn total_funded(&self) -> U256 {
+        self.funders
+            .values()
+            .fold(U256::zero(), |total, amount| total.saturating_add(*amount))
+    }
+
+    pub fn keeper_rewards(&self, keeper: H160) -> U256 {
+        self.keeper_rewards
+            .get(&keeper)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use treasury::treasury::{
+        AddressChangeRequested, EscrowTerms, EscrowTimeout, OneTimeData, PayoutAmendment,
+        PayoutShare, PayoutStatus, ResumePolicy, StoredOneTimePayout, Treasury,
+    };
+
+    /// Capture the events recorded since the last capture as fixtures for `block`
+    fn capture(fixtures: &mut Vec<RawEvent>, block: u32) {
+        let recorded = ink::env::test::recorded_events().collect::<Vec<_>>();
+        for event in recorded.into_iter().skip(fixtures.len()) {
+            fixtures.push(RawEvent {
+                block,
+                topics: event
+                    .topics
+                    .iter()
+                    .map(|topic| topic.as_slice().try_into().unwrap())
+                    .collect(),
+                data: event.data,
+            });
+        }
+    }
+
+    fn raw_event<E: ink::env::Event>(block: u32, event: &E) -> RawEvent {
+        RawEvent {
+            block,
+            topics: E::SIGNATURE_TOPIC.into_iter().collect(),
+            data: event.encode(),
+        }
+    }
+
+    fn set_block(block: u32) {
+        ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(block);
+    }
+
+    #[ink::test]
+    fn ledger_follows_contract_state() {
+        let accounts = ink::env::test::default_accounts();
+        ink::env::test::set_caller(accounts.alice);
+        let mut fixtures = Vec::new();
+
+        set_block(1);
+        let mut treasury = Treasury::new();
+        ink::env::test::set_account_balance(ink::env::address(), U256::from(100_000_000));
+        treasury
+            .add_payout(accounts.bob, U256::from(1_000_000), None)
+            .unwrap();
+        let recurring_id = treasury
+            .add_recurring_payout(accounts.charlie, U256::from(2_000_000), Some(1), 5, 2)
+            .unwrap();
+        let cancelled_id = treasury
+            .add_payout(accounts.django, U256::from(3_000_000), Some(50))
+            .unwrap();
+        let scheduled_id = treasury
+            .add_payout(accounts.frank, U256::from(4_000_000), Some(100))
+            .unwrap();
+        treasury
+            .amend_payout(
+                scheduled_id,
+                PayoutAmendment {
+                    to: Some(accounts.eve),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        treasury
+            .set_payout_shares(
+                scheduled_id,
+                vec![
+                    PayoutShare {
+                        to: accounts.eve,
+                        basis_points: 5_000,
+                    },
+                    PayoutShare {
+                        to: accounts.frank,
+                        basis_points: 5_000,
+                    },
+                ],
+            )
+            .unwrap();
+        treasury.pause_payout(scheduled_id).unwrap();
+        treasury.process_payouts().unwrap();
+        treasury.cancel_payout(cancelled_id).unwrap();
+        capture(&mut fixtures, 1);
+
+        set_block(6);
+        treasury
+            .resume_payout(scheduled_id, ResumePolicy::Shift)
+            .unwrap();
+        ink::env::test::set_caller(accounts.eve);
+        treasury.request_address_change(accounts.frank).unwrap();
+        ink::env::test::set_caller(accounts.alice);
+        treasury.confirm_address_change(accounts.eve).unwrap();
+        treasury.process_payouts().unwrap();
+        capture(&mut fixtures, 6);
+
+        let mut ledger = Ledger::new();
+        ledger.apply_all(&fixtures).unwrap();
+
+        assert_eq!(ledger.owner(), Some(accounts.alice));
+        assert_eq!(ledger.last_block(), 6);
+        assert_eq!(
+            ledger.pending_payout_ids(),
+            treasury.get_pending_payout_ids().as_slice()
+        );
+        assert_eq!(ledger.pending_payout_ids(), &[scheduled_id]);
+        let amended = ledger.payout(scheduled_id).unwrap();
+        assert_eq!(amended.to, accounts.frank);
+        assert_eq!(amended.revisions.len(), 1);
+        assert_eq!(amended.state, PayoutState::Pending);
+        assert_eq!(amended.shares, treasury.get_payout_shares(scheduled_id));
+        assert_eq!(
+            Some(&amended.payout),
+            treasury.get_payout(scheduled_id).as_ref()
+        );
+        assert_eq!(
+            ledger.payout(cancelled_id).unwrap().state,
+            PayoutState::Cancelled(1)
+        );
+        assert_eq!(
+            ledger.payout(recurring_id).unwrap().state,
+            PayoutState::Completed(1)
+        );
+
+        // The recurring payout paid twice, the second time through its follow-up
+        let installments: Vec<(u32, u32)> = ledger
+            .payments_to(accounts.charlie)
+            .iter()
+            .map(|payment| (payment.installment, payment.block))
+            .collect();
+        assert_eq!(installments, vec![(1, 1), (2, 6)]);
+        assert_eq!(
+            ledger.total_paid_to(accounts.charlie),
+            vec![(Asset::Native, U256::from(4_000_000))]
+        );
+        let follow_up = ledger.payouts_for(accounts.charlie)[1];
+        assert_eq!(follow_up.previous_payout_id, Some(recurring_id));
+        assert_eq!(follow_up.payout_type, PayoutType::Recurring);
+
+        let mut recipients = vec![
+            accounts.bob,
+            accounts.charlie,
+            accounts.django,
+            accounts.frank,
+        ];
+        recipients.sort();
+        assert_eq!(ledger.recipients(), recipients);
+        assert_eq!(ledger.batches().len(), 2);
+        assert_eq!(
+            ledger.batches()[0].asset_totals,
+            vec![(Asset::Native, U256::from(3_000_000))]
+        );
+        let paid: U256 = ledger
+            .payments()
+            .iter()
+            .fold(U256::zero(), |total, payment| total + payment.amount);
+        assert_eq!(
+            paid,
+            ledger
+                .batches()
+                .iter()
+                .flat_map(|batch| batch.asset_totals.iter())
+                .fold(U256::zero(), |total, (_, amount)| total + *amount)
+        );
+    }
+
+    fn one_time_added(payout_id: u32, to: H160, asset: Asset, amount: u128) -> RawEvent {
+        let payout_data = Payout::OneTime(StoredOneTimePayout {
+            data: OneTimeData {
+                to,
+                asset,
+                amount: U256::from(amount),
+                scheduled_block: None,
+                quote: None,
+                time_schedule: None,
+            },
+            id: payout_id,
+            created_block: 1,
+            status: PayoutStatus::Pending,
+        });
+        raw_event(
+            1,
+            &PayoutAdded {
+                payout_id,
+                to,
+                payout_type: PayoutType::OneTime,
+                amount: U256::from(amount),
+                payout_data,
+            },
+        )
+    }
+
+    fn paid(block: u32, payout_id: u32, to: H160, amount: u128) -> RawEvent {
+        raw_event(
+            block,
+            &PayoutPaid {
+                payout_id,
+                to,
+                amount: U256::from(amount),
+                installment: 1,
+            },
+        )
+    }
+
+    #[test]
+    fn totals_are_kept_per_asset() {
+        let bob = H160::from([2u8; 20]);
+        let charlie = H160::from([3u8; 20]);
+        let arbiter = H160::from([4u8; 20]);
+        let token = Asset::Token(H160::from([9u8; 20]));
+        let escrow = |to: H160, amount: u128| Escrow {
+            terms: EscrowTerms {
+                to,
+                asset: Asset::Native,
+                amount: U256::from(amount),
+                arbiters: vec![arbiter],
+                quorum: 1,
+                timeout_block: 100,
+                on_timeout: EscrowTimeout::Refund,
+            },
+            status: EscrowStatus::Funded,
+            release_votes: Vec::new(),
+            refund_votes: Vec::new(),
+        };
+
+        let mut ledger = Ledger::new();
+        ledger
+            .apply_all(&[
+                one_time_added(7, bob, Asset::Native, 1_000_000),
+                one_time_added(8, bob, token, 20_000_000),
+                raw_event(
+                    1,
+                    &V6PayoutsImported {
+                        imported_ids: vec![5, 7, 8],
+                    },
+                ),
+                paid(2, 7, bob, 1_000_000),
+                paid(2, 8, bob, 20_000_000),
+                raw_event(
+                    2,
+                    &DistributionCreated {
+                        distribution_id: 0,
+                        distribution: Distribution {
+                            asset: token,
+                            merkle_root: [1u8; 32],
+                            total_amount: U256::from(9_000_000),
+                            claimed_amount: U256::zero(),
+                            recipient_count: 2,
+                            expiry_block: 10,
+                            closed: false,
+                        },
+                    },
+                ),
+                raw_event(
+                    3,
+                    &DistributionClaimed {
+                        distribution_id: 0,
+                        to: bob,
+                        index: 1,
+                        amount: U256::from(5_000_000),
+                    },
+                ),
+                raw_event(
+                    11,
+                    &DistributionClosed {
+                        distribution_id: 0,
+                        unclaimed_amount: U256::from(4_000_000),
+                    },
+                ),
+                raw_event(
+                    11,
+                    &EscrowCreated {
+                        escrow_id: 0,
+                        to: charlie,
+                        escrow: escrow(charlie, 3_000_000),
+                    },
+                ),
+                raw_event(
+                    11,
+                    &EscrowCreated {
+                        escrow_id: 1,
+                        to: bob,
+                        escrow: escrow(bob, 6_000_000),
+                    },
+                ),
+                // Tracked only through the change it leads to
+                raw_event(
+                    12,
+                    &AddressChangeRequested {
+                        from: charlie,
+                        to: bob,
+                        unlock_block: None,
+                    },
+                ),
+                raw_event(
+                    12,
+                    &RecipientAddressChanged {
+                        from: charlie,
+                        to: bob,
+                        payout_ids: Vec::new(),
+                        escrow_ids: vec![0],
+                    },
+                ),
+                raw_event(
+                    13,
+                    &EscrowVoted {
+                        escrow_id: 0,
+                        arbiter,
+                        release: true,
+                    },
+                ),
+                raw_event(
+                    13,
+                    &EscrowReleased {
+                        escrow_id: 0,
+                        to: bob,
+                        amount: U256::from(3_000_000),
+                        by_timeout: false,
+                    },
+                ),
+                raw_event(
+                    14,
+                    &EscrowDisputed {
+                        escrow_id: 1,
+                        raised_by: bob,
+                    },
+                ),
+                raw_event(
+                    15,
+                    &EscrowRefunded {
+                        escrow_id: 1,
+                        amount: U256::from(6_000_000),
+                        by_timeout: true,
+                    },
+                ),
+            ])
+            .unwrap();
+
+        assert_eq!(ledger.payments()[1].asset, token);
+        // The refunded escrow never reached bob
+        assert_eq!(
+            ledger.total_paid_to(bob),
+            vec![
+                (Asset::Native, U256::from(4_000_000)),
+                (token, U256::from(25_000_000)),
+            ]
+        );
+        assert!(ledger.total_paid_to(charlie).is_empty());
+        assert_eq!(ledger.imported_payout_ids(), &[5, 7, 8]);
+        assert!(ledger.payout(5).is_none());
+
+        let distribution = ledger.distribution(0).unwrap();
+        assert_eq!(
+            distribution.distribution.claimed_amount,
+            U256::from(5_000_000)
+        );
+        assert!(distribution.distribution.closed);
+        assert_eq!(distribution.closed_block, Some(11));
+        let released = &ledger.escrow(0).unwrap().escrow;
+        assert_eq!(released.status, EscrowStatus::Released(13));
+        assert_eq!(released.release_votes, vec![arbiter]);
+        assert_eq!(
+            ledger.escrow(1).unwrap().escrow.status,
+            EscrowStatus::Refunded(15)
+        );
+        assert_eq!(ledger.last_block(), 15);
+    }
+
+    #[test]
+    fn funders_and_keepers_are_summed() {
+        let funder = H160::from([1u8; 20]);
+        let keeper = H160::from([2u8; 20]);
+        let mut ledger = Ledger::new();
+        ledger
+            .apply_all(&[
+                raw_event(
+                    3,
+                    &FundsAdded {
+                        from: funder,
+                        amount: U256::from(5),
+                    },
+                ),
+                raw_event(
+                    4,
+                    &FundsAdded {
+                        from: funder,
+                        amount: U256::from(7),
+                    },
+                ),
+                raw_event(
+                    4,
+                    &KeeperRewarded {
+                        keeper,
+                        amount: U256::from(2),
+                    },
+                ),
+            ])
+            .unwrap();
+
+        assert_eq!(ledger.funders(), vec![(funder, U256::from(12))]);
+        assert_eq!(ledger.total_funded(), U256::from(12));
+        assert_eq!(ledger.keeper_rewards(keeper), U256::from(2));
+        assert_eq!(ledger.last_block(), 4);
+    }
+
+    #[test]
+    fn rejects_invalid_and_dangling_events() {
+        let mut ledger = Ledger::new();
+
+        // Untracked and anonymous events are skipped
+        let untracked = RawEvent {
+            block: 1,
+            topics: vec![[7u8; 32]],
+            data: vec![1, 2, 3],
+        };
+        assert!(ledger.apply(&untracked).is_ok());
+
+        let mut truncated = raw_event(
+            2,
+            &FundsAdded {
+                from: H160::zero(),
+                amount: U256::from(1),
+            },
+        );
+        truncated.data.pop();
+        assert_eq!(
+            ledger.apply(&truncated),
+            Err(IndexerError::InvalidEventData {
+                block: 2,
+                event: "FundsAdded"
+            })
+        );
+
+        let completed = raw_event(
+            3,
+            &PayoutCompleted {
+                payout_id: 9,
+                to: H160::zero(),
+            },
+        );
+        assert_eq!(
+            ledger.apply(&completed),
+            Err(IndexerError::UnknownPayout(9))
+        );
+
+        let voted = raw_event(
+            4,
+            &EscrowVoted {
+                escrow_id: 2,
+                arbiter: H160::zero(),
+                release: true,
+            },
+        );
+        assert_eq!(ledger.apply(&voted), Err(IndexerError::UnknownEscrow(2)));
+    }
+}